@@ -0,0 +1,137 @@
+use std::fmt;
+
+/// byte order of the words of the target
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Endian {
+    /// least significant byte first
+    Little,
+    /// most significant byte first
+    Big,
+}
+
+/// Target the dump has been taken from: the width of its pointers,
+/// which is the size of a word, and their byte order.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Target {
+    word: u64,
+    endian: Endian,
+}
+
+impl Target {
+    /// target with pointers `pointer_width` bits wide,
+    /// only 16, 32 and 64 bits targets are supported
+    pub fn new(pointer_width: u32, endian: Endian) -> Option<Self> {
+        matches!(pointer_width, 16 | 32 | 64).then_some(Target {
+            word: (pointer_width / 8) as u64,
+            endian,
+        })
+    }
+
+    /// target the inspector runs on
+    pub fn host() -> Self {
+        let endian = match cfg!(target_endian = "big") {
+            true => Endian::Big,
+            false => Endian::Little,
+        };
+
+        Target {
+            word: size_of::<usize>() as u64,
+            endian,
+        }
+    }
+
+    /// size of a word, in bytes
+    #[inline]
+    pub fn word(&self) -> u64 {
+        self.word
+    }
+
+    /// size of a word, in bits
+    #[inline]
+    pub fn bits(&self) -> u32 {
+        self.word as u32 * 8
+    }
+
+    /// rounds `address` up to a multiple of the word size
+    pub fn align_up(&self, address: u64) -> u64 {
+        address.next_multiple_of(self.word)
+    }
+}
+
+/// Raw memory image, covering the addresses from `start`
+/// up to `start` plus its length.
+pub struct Dump {
+    bytes: Vec<u8>,
+    start: u64,
+    target: Target,
+}
+
+impl Dump {
+    /// image of the memory starting at address `start`
+    pub fn new(bytes: Vec<u8>, start: u64, target: Target) -> Self {
+        Dump {
+            bytes,
+            start,
+            target,
+        }
+    }
+
+    #[inline]
+    pub fn target(&self) -> Target {
+        self.target
+    }
+
+    /// first address of the image
+    #[inline]
+    pub fn start(&self) -> u64 {
+        self.start
+    }
+
+    /// first address after the image
+    #[inline]
+    pub fn end(&self) -> u64 {
+        self.start + self.bytes.len() as u64
+    }
+
+    /// whether the `len` bytes at `address` are all in the image
+    pub fn contains(&self, address: u64, len: u64) -> bool {
+        address >= self.start
+            && address
+                .checked_add(len)
+                .is_some_and(|end| end <= self.end())
+    }
+
+    /// Word at `address`, if in the image. Words are read as they are,
+    /// whether aligned or not.
+    pub fn word(&self, address: u64) -> Option<u64> {
+        if !self.contains(address, self.target.word) {
+            return None;
+        }
+
+        let offset = (address - self.start) as usize;
+        let bytes = &self.bytes[offset..offset + self.target.word as usize];
+        let fold = |word: u64, byte: &u8| (word << 8) | *byte as u64;
+
+        Some(match self.target.endian {
+            Endian::Big => bytes.iter().fold(0, fold),
+            Endian::Little => bytes.iter().rev().fold(0, fold),
+        })
+    }
+
+    /// every aligned word of the image, along with its address
+    pub fn words(&self) -> impl Iterator<Item = (u64, u64)> + '_ {
+        let first = self.target.align_up(self.start);
+        let addresses = (first..self.end()).step_by(self.target.word as usize);
+        addresses.filter_map(|address| Some((address, self.word(address)?)))
+    }
+}
+
+impl fmt::Debug for Dump {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Dump")
+            .field("start", &format_args!("{:#x}", self.start))
+            .field("end", &format_args!("{:#x}", self.end()))
+            .field("target", &self.target)
+            .finish()
+    }
+}
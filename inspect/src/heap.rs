@@ -0,0 +1,330 @@
+use crate::dump::Dump;
+use palloc::layout;
+use std::{collections::HashSet, fmt};
+
+/// State of a block, from its flags.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum State {
+    /// allocation in use
+    Used,
+    /// free block, the tail included
+    Free,
+    /// released, but kept aside by an allocator built on top of the chain
+    Cached,
+    /// header linking a region to the next one
+    Fence,
+}
+
+/// Block of the chain, as found in the dump.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Block {
+    /// address of the header
+    pub header: u64,
+    /// address of the payload, right after the header
+    pub payload: u64,
+    /// size of the allocation, kept once the block is freed
+    pub size: u64,
+    /// bytes up to the next header, unknown for the tail
+    /// of a region whose end is not known
+    pub capacity: Option<u64>,
+    /// state, from the flags
+    pub state: State,
+    /// whether the flag of the previous block being free is set
+    pub prev_free: bool,
+    /// whether the block is the tail, the last one of the chain
+    pub tail: bool,
+}
+
+/// What is wrong with a block, see [`Problem`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProblemKind {
+    /// the header lies outside of the dump
+    OutsideDump,
+    /// the block links to a misaligned address, out of its region,
+    /// or to a region already walked over
+    InvalidLink {
+        /// address of the next header
+        next: u64,
+    },
+    /// the block links to an address lower than its own
+    LinkNotIncreasing {
+        /// address of the next header
+        next: u64,
+    },
+    /// the allocation does not fit in the block
+    SizeOverflow,
+    /// the free block is too small to hold its free list links
+    TooSmall,
+    /// the boundary tag of the free block, or the flag
+    /// of the following one, is missing or wrong
+    BadTag,
+    /// the free block is followed by another free block
+    Unmerged,
+    /// the tail is allocated
+    AllocatedTail,
+}
+
+/// Corruption found at the block whose header lies at `at`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Problem {
+    /// header address of the corrupted block
+    pub at: u64,
+    /// what is wrong with it
+    pub kind: ProblemKind,
+}
+
+impl fmt::Display for Problem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:#x}: ", self.at)?;
+        match self.kind {
+            ProblemKind::OutsideDump => f.write_str("header outside of the dump"),
+            ProblemKind::InvalidLink { next } => write!(f, "invalid link to {:#x}", next),
+            ProblemKind::LinkNotIncreasing { next } => {
+                write!(f, "link to {:#x} goes backwards", next)
+            }
+            ProblemKind::SizeOverflow => f.write_str("allocation larger than its block"),
+            ProblemKind::TooSmall => f.write_str("free block too small"),
+            ProblemKind::BadTag => f.write_str("wrong boundary tag"),
+            ProblemKind::Unmerged => f.write_str("free block followed by another one"),
+            ProblemKind::AllocatedTail => f.write_str("allocated tail"),
+        }
+    }
+}
+
+/// Totals of a heap, see [`Heap::totals`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Totals {
+    /// bytes of the allocations in use, as requested
+    pub used: u64,
+    /// bytes of the free and cached blocks, the tail included if its end is known
+    pub free: u64,
+    /// bytes of the headers, along with the unused end of allocated blocks
+    pub overhead: u64,
+    /// number of allocations in use
+    pub used_blocks: usize,
+    /// number of free blocks, the tail included
+    pub free_blocks: usize,
+    /// number of cached blocks
+    pub cached_blocks: usize,
+    /// number of fences, one less than the number of regions
+    pub fences: usize,
+}
+
+/// Block chain of a heap rebuilt from a dump, along with the
+/// corruption found along the way.
+#[derive(Debug)]
+pub struct Heap {
+    /// blocks from the bottom of the heap, up to the tail or the
+    /// first link that cannot be followed
+    pub blocks: Vec<Block>,
+    /// corruption found, in chain order
+    pub problems: Vec<Problem>,
+}
+
+impl Heap {
+    /// Rebuilds the chain of the heap initialized with `size` bytes at `base`,
+    /// following the links across regions. Walking stops at the tail, or at
+    /// the first link leading outside of the dump, out of its region or
+    /// backwards. Other problems are reported along the way.
+    pub fn walk(dump: &Dump, base: u64, size: u64) -> Heap {
+        let target = dump.target();
+        let word = target.word();
+        let header_size = layout::HEADER_WORDS as u64 * word;
+        let min_capacity = layout::MIN_CAPACITY_WORDS as u64 * word;
+
+        let (mut blocks, mut problems) = (Vec::new(), Vec::new());
+        let mut problem = |at, kind| problems.push(Problem { at, kind });
+
+        // end of the current region, only known for the first one
+        let mut end = Some(base.saturating_add(size));
+        let mut header = target.align_up(base);
+        let mut regions = HashSet::from([header]);
+        let mut previous: Option<Block> = None;
+
+        loop {
+            let Some(mut block) = read(dump, header) else {
+                problem(header, ProblemKind::OutsideDump);
+                break;
+            };
+            let next = dump.word(header + layout::NEXT_WORD as u64 * word).unwrap();
+
+            // the flag and the tag left by the preceding block, if free
+            if let Some(previous) = previous {
+                let free = previous.state == State::Free;
+                let tagged = dump.word(header - word) == Some(previous.header);
+                if block.prev_free != free || (free && !tagged) {
+                    problem(previous.header, ProblemKind::BadTag);
+                }
+                if free && block.state == State::Free {
+                    problem(previous.header, ProblemKind::Unmerged);
+                }
+            }
+
+            if next == 0 {
+                block.tail = true;
+                block.capacity = end.map(|end| end.saturating_sub(block.payload));
+                if block.state != State::Free {
+                    problem(header, ProblemKind::AllocatedTail);
+                }
+
+                blocks.push(block);
+                break;
+            }
+
+            // regions are only linked by fences, anywhere in memory
+            let fence = block.state == State::Fence;
+            let in_region = end.is_none_or(|end| next.saturating_add(header_size) <= end);
+            let kind = match next {
+                next if next % word != 0 || !dump.contains(next, header_size) => {
+                    Some(ProblemKind::InvalidLink { next })
+                }
+                next if fence && !regions.insert(next) => Some(ProblemKind::InvalidLink { next }),
+                next if !fence && next < block.payload => {
+                    Some(ProblemKind::LinkNotIncreasing { next })
+                }
+                next if !fence && !in_region => Some(ProblemKind::InvalidLink { next }),
+                _ => None,
+            };
+            if let Some(kind) = kind {
+                problem(header, kind);
+                blocks.push(block);
+                break;
+            }
+
+            let capacity = next.saturating_sub(block.payload);
+            if !fence {
+                block.capacity = Some(capacity);
+            }
+            match block.state {
+                State::Used | State::Cached if block.size > capacity => {
+                    problem(header, ProblemKind::SizeOverflow)
+                }
+                State::Free if capacity < min_capacity => problem(header, ProblemKind::TooSmall),
+                _ => {}
+            }
+
+            if fence {
+                end = None;
+            }
+
+            blocks.push(block);
+            previous = Some(block);
+            header = next;
+        }
+
+        Heap { blocks, problems }
+    }
+
+    /// bytes and blocks by state, see [`Totals`]
+    pub fn totals(&self, dump: &Dump) -> Totals {
+        let header = layout::HEADER_WORDS as u64 * dump.target().word();
+
+        let mut totals = Totals::default();
+        for block in &self.blocks {
+            totals.overhead += header;
+            let capacity = block.capacity.unwrap_or(0);
+
+            match block.state {
+                State::Used => {
+                    totals.used += block.size;
+                    totals.overhead += capacity.saturating_sub(block.size);
+                    totals.used_blocks += 1;
+                }
+                State::Free => {
+                    totals.free += capacity;
+                    totals.free_blocks += 1;
+                }
+                State::Cached => {
+                    totals.free += capacity;
+                    totals.cached_blocks += 1;
+                }
+                State::Fence => totals.fences += 1,
+            }
+        }
+
+        totals
+    }
+
+    /// Allocations in use that no word of the dump points into, but their own
+    /// ones, sorted by address. Words within free and cached blocks are stale,
+    /// so they are not taken into account either.
+    ///
+    /// Pointers kept in registers or outside of the dump are not seen, and
+    /// allocations only pointing to each other are not reported: these are
+    /// suspected leaks, meaningful once the dump covers the stacks and the
+    /// statics of the program.
+    pub fn leaks(&self, dump: &Dump) -> Vec<Block> {
+        let payload = |block: &Block| block.payload..block.payload + block.capacity.unwrap_or(0);
+
+        // regions may lie anywhere in memory, the chain is not sorted
+        let sorted = |filter: fn(&&Block) -> bool| {
+            let mut blocks: Vec<&Block> = self.blocks.iter().filter(filter).collect();
+            blocks.sort_unstable_by_key(|block| block.payload);
+            blocks
+        };
+        let used = sorted(|block| block.state == State::Used);
+        let stale = sorted(|block| block.is_free());
+        let containing = |blocks: &[&Block], address: u64| {
+            let index = blocks.partition_point(|block| block.payload <= address);
+            index
+                .checked_sub(1)
+                .filter(|index| payload(blocks[*index]).contains(&address))
+        };
+
+        let mut referenced = vec![false; used.len()];
+        for (address, value) in dump.words() {
+            if containing(&stale, address).is_some() {
+                continue;
+            }
+
+            match containing(&used, value) {
+                Some(index) if !payload(used[index]).contains(&address) => referenced[index] = true,
+                _ => {}
+            }
+        }
+
+        let leaks = used.into_iter().zip(referenced);
+        leaks
+            .filter(|(_, referenced)| !referenced)
+            .map(|(block, _)| *block)
+            .collect()
+    }
+}
+
+impl Block {
+    /// whether the memory of the block is free, cached or not
+    pub fn is_free(&self) -> bool {
+        matches!(self.state, State::Free | State::Cached)
+    }
+}
+
+/// header at `address`, its link left aside, if in the dump
+fn read(dump: &Dump, address: u64) -> Option<Block> {
+    let target = dump.target();
+    let word = target.word();
+    let header_size = layout::HEADER_WORDS as u64 * word;
+    if !dump.contains(address, header_size) {
+        return None;
+    }
+
+    let allocation = dump.word(address + layout::SIZE_WORD as u64 * word)?;
+    let flagged = |bit| allocation & layout::flag(bit, target.bits()) != 0;
+    let size = allocation & (u64::MAX >> (64 - target.bits() + layout::FLAG_BITS));
+
+    let state = match flagged(layout::ALLOCATED_BIT) {
+        false => State::Free,
+        true if flagged(layout::FENCE_BIT) => State::Fence,
+        true if flagged(layout::CACHED_BIT) => State::Cached,
+        true => State::Used,
+    };
+
+    Some(Block {
+        header: address,
+        payload: address + header_size,
+        size,
+        capacity: None,
+        state,
+        prev_free: flagged(layout::PREV_FREE_BIT),
+        tail: false,
+    })
+}
@@ -0,0 +1,18 @@
+//! Host-side inspector of [`palloc`] heaps in raw memory dumps
+//!
+//! A [`Dump`] is an image of the memory of a target, taken after a crash for
+//! instance. Given the address and the size the heap has been initialized
+//! with, [`Heap::walk`] rebuilds its block chain following the layout
+//! documented in [`palloc::layout`], reporting the corruption found along
+//! the way, while [`Heap::leaks`] looks for allocations nothing points to.
+//!
+//! The `palloc-inspect` binary prints all of it, see its `--help`.
+
+mod dump;
+pub use crate::dump::{Dump, Endian, Target};
+
+mod heap;
+pub use crate::heap::{Block, Heap, Problem, ProblemKind, State, Totals};
+
+#[cfg(test)]
+mod test;
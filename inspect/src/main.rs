@@ -0,0 +1,171 @@
+use palloc::layout;
+use palloc_inspect::{Dump, Endian, Heap, State, Target};
+use std::{env, fs, process::ExitCode};
+
+const USAGE: &str = "\
+usage: palloc-inspect <dump> --base <address> --size <bytes> --width <16|32|64> [options]
+
+Rebuilds the block chain of a palloc heap from a raw memory dump, and prints
+its blocks, the used and free totals, the suspected leaks and the corruption
+found. Exits with 1 when the heap is corrupted.
+
+    --base <address>        address the heap has been initialized with
+    --size <bytes>          size the heap has been initialized with
+    --width <bits>          pointer width of the target
+    --dump-start <address>  address of the first byte of the dump, 0 by default
+    --big-endian            the target is big endian
+    --quiet                 print the totals and the problems only
+
+Numbers may be given in hexadecimal with a 0x prefix.";
+
+/// command line, see [`USAGE`]
+struct Options {
+    dump: String,
+    base: u64,
+    size: u64,
+    target: Target,
+    dump_start: u64,
+    quiet: bool,
+}
+
+fn main() -> ExitCode {
+    if env::args().any(|arg| arg == "-h" || arg == "--help") {
+        println!("{}", USAGE);
+        return ExitCode::SUCCESS;
+    }
+
+    let options = match parse(env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("palloc-inspect: {}\n\n{}", err, USAGE);
+            return ExitCode::from(2);
+        }
+    };
+
+    let bytes = match fs::read(&options.dump) {
+        Ok(bytes) => bytes,
+        Err(err) => {
+            eprintln!("palloc-inspect: cannot read {}: {}", options.dump, err);
+            return ExitCode::from(2);
+        }
+    };
+
+    let dump = Dump::new(bytes, options.dump_start, options.target);
+    let heap = Heap::walk(&dump, options.base, options.size);
+    print(&options, &dump, &heap);
+
+    match heap.problems.is_empty() {
+        true => ExitCode::SUCCESS,
+        false => ExitCode::FAILURE,
+    }
+}
+
+fn parse(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let (mut dump, mut base, mut size, mut width) = (None, None, None, None);
+    let (mut dump_start, mut endian, mut quiet) = (0, Endian::Little, false);
+
+    while let Some(arg) = args.next() {
+        let mut value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "--base" => base = Some(number(&value()?)?),
+            "--size" => size = Some(number(&value()?)?),
+            "--width" => width = Some(number(&value()?)?),
+            "--dump-start" => dump_start = number(&value()?)?,
+            "--big-endian" => endian = Endian::Big,
+            "--quiet" => quiet = true,
+            flag if flag.starts_with("--") => return Err(format!("unknown option {}", flag)),
+            _ if dump.is_none() => dump = Some(arg),
+            _ => return Err(format!("unexpected argument {}", arg)),
+        }
+    }
+
+    let width = width.ok_or("missing --width")?;
+    let target = u32::try_from(width)
+        .ok()
+        .and_then(|width| Target::new(width, endian))
+        .ok_or(format!("unsupported pointer width {}", width))?;
+
+    Ok(Options {
+        dump: dump.ok_or("missing dump file")?,
+        base: base.ok_or("missing --base")?,
+        size: size.ok_or("missing --size")?,
+        target,
+        dump_start,
+        quiet,
+    })
+}
+
+fn number(value: &str) -> Result<u64, String> {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => u64::from_str_radix(hex, 16),
+        None => value.parse(),
+    };
+
+    parsed.map_err(|_| format!("invalid number {}", value))
+}
+
+fn print(options: &Options, dump: &Dump, heap: &Heap) {
+    println!(
+        "heap {:#x}..{:#x}, {}-bit words, layout version {}",
+        options.base,
+        options.base.saturating_add(options.size),
+        options.target.bits(),
+        layout::VERSION
+    );
+
+    if !options.quiet {
+        println!();
+        println!(
+            "  {:<18}  {:<18}  {:>10}  {:>10}  state",
+            "header", "payload", "size", "capacity"
+        );
+        for block in &heap.blocks {
+            let state = match (block.state, block.tail) {
+                (State::Free, true) => "free (tail)",
+                (State::Free, false) => "free",
+                (State::Used, _) => "used",
+                (State::Cached, _) => "cached",
+                (State::Fence, _) => "fence",
+            };
+            let capacity = block
+                .capacity
+                .map_or("-".into(), |capacity| capacity.to_string());
+
+            println!(
+                "  {:<#18x}  {:<#18x}  {:>10}  {:>10}  {}",
+                block.header, block.payload, block.size, capacity, state
+            );
+        }
+    }
+
+    let totals = heap.totals(dump);
+    println!();
+    println!(
+        "blocks: {} used, {} free, {} cached, {} fences",
+        totals.used_blocks, totals.free_blocks, totals.cached_blocks, totals.fences
+    );
+    println!(
+        "bytes: {} used, {} free, {} overhead",
+        totals.used, totals.free, totals.overhead
+    );
+
+    let leaks = heap.leaks(dump);
+    let leaked: u64 = leaks.iter().map(|block| block.size).sum();
+    println!();
+    println!("suspected leaks: {} blocks, {} bytes", leaks.len(), leaked);
+    for block in &leaks {
+        println!(
+            "  {:#x}: {} bytes, not pointed to",
+            block.payload, block.size
+        );
+    }
+
+    println!();
+    match heap.problems.is_empty() {
+        true => println!("corruption: none found"),
+        false => println!("corruption:"),
+    }
+    for problem in &heap.problems {
+        println!("  {}", problem);
+    }
+}
@@ -0,0 +1,244 @@
+use crate::{Block, Dump, Endian, Heap, Problem, ProblemKind, State, Target};
+use palloc::{Palloc, SegregatedPalloc};
+use std::{mem::size_of, ptr::NonNull};
+
+const WORD: u64 = size_of::<usize>() as u64;
+const HEADER: u64 = 2 * WORD;
+
+#[repr(C, align(64))]
+struct Memory([u8; 1024]);
+
+impl Memory {
+    fn base(&self) -> u64 {
+        self.0.as_ptr() as u64
+    }
+
+    /// image of the heap, as pulled from the device
+    fn dump(&self) -> Dump {
+        Dump::new(self.0.to_vec(), self.base(), Target::host())
+    }
+}
+
+fn header(alloc: NonNull<u8>) -> u64 {
+    alloc.as_ptr() as u64 - HEADER
+}
+
+#[test]
+fn test_walk() {
+    let mut heap = Memory([0; 1024]);
+    let mut palloc = Palloc::empty();
+    unsafe { palloc.init_from_slice(&mut heap.0) };
+
+    let allocations = [24, 100, 8, 60].map(|size| unsafe { palloc.alloc(size).unwrap() });
+    unsafe { palloc.free(allocations[1]).unwrap() };
+    let expected: Vec<_> = palloc.blocks().collect();
+
+    let dump = heap.dump();
+    let walked = Heap::walk(&dump, heap.base(), 1024);
+    assert_eq!(walked.problems, []);
+    assert_eq!(walked.blocks.len(), expected.len());
+    for (block, info) in walked.blocks.iter().zip(expected) {
+        assert_eq!(block.header, info.header_addr as u64);
+        assert_eq!(block.capacity, Some(info.capacity as u64));
+        assert_eq!(block.state == State::Used, info.allocated);
+    }
+
+    let totals = walked.totals(&dump);
+    assert_eq!(
+        (totals.used, totals.used_blocks, totals.free_blocks),
+        (92, 3, 2)
+    );
+    assert_eq!(totals.used + totals.free + totals.overhead, 1024);
+}
+
+#[test]
+fn test_leaks() {
+    let mut heap = Memory([0; 1024]);
+    let mut palloc = Palloc::empty();
+    unsafe { palloc.init_from_slice(&mut heap.0) };
+
+    // the first allocation points into the second one, the third one is
+    // only pointed to from freed memory, the fourth one from nowhere
+    let allocations = [32, 32, 32, 32, 32].map(|size| unsafe { palloc.alloc(size).unwrap() });
+    unsafe {
+        *allocations[0].cast::<u64>().as_ptr() = allocations[1].as_ptr() as u64 + 8;
+        *allocations[4].cast::<u64>().as_ptr().add(2) = allocations[2].as_ptr() as u64;
+        palloc.free(allocations[4]).unwrap();
+    }
+
+    let dump = heap.dump();
+    let walked = Heap::walk(&dump, heap.base(), 1024);
+    let leaks: Vec<u64> = walked
+        .leaks(&dump)
+        .iter()
+        .map(|block| block.header)
+        .collect();
+    assert_eq!(
+        leaks,
+        [
+            header(allocations[0]),
+            header(allocations[2]),
+            header(allocations[3])
+        ]
+    );
+}
+
+#[test]
+fn test_leaks_across_regions() {
+    let mut heap = Memory([0; 1024]);
+    let mut palloc = Palloc::empty();
+    let base = heap.base();
+
+    // the second region lies below the first one, the allocations
+    // following the first one are placed there
+    let (first, allocations) = unsafe {
+        let bottom = NonNull::new_unchecked(heap.0.as_mut_ptr());
+        palloc.init(bottom.add(512), 512);
+        let first = palloc.alloc(400).unwrap();
+        palloc.add_region(bottom, 512);
+        (
+            first,
+            [100, 100, 100].map(|size| palloc.alloc(size).unwrap()),
+        )
+    };
+    assert!(allocations.iter().all(|alloc| alloc < &first));
+
+    // the first allocation points into the second one, which points to
+    // the third one, the first and the last ones are not pointed to
+    unsafe {
+        *first.cast::<u64>().as_ptr() = allocations[0].as_ptr() as u64 + 8;
+        *allocations[0].cast::<u64>().as_ptr() = allocations[1].as_ptr() as u64;
+    }
+
+    let dump = heap.dump();
+    let walked = Heap::walk(&dump, base + 512, 512);
+    assert_eq!(walked.problems, []);
+    let leaks: Vec<u64> = walked
+        .leaks(&dump)
+        .iter()
+        .map(|block| block.header)
+        .collect();
+    assert_eq!(leaks, [header(allocations[2]), header(first)]);
+}
+
+#[test]
+fn test_regions() {
+    let mut heap = Memory([0; 1024]);
+    let mut palloc = SegregatedPalloc::empty();
+    let base = heap.base();
+    unsafe {
+        let bottom = NonNull::new_unchecked(heap.0.as_mut_ptr());
+        palloc.init(bottom.add(512), 512);
+        palloc.alloc(400).unwrap();
+        palloc.add_region(bottom, 512);
+
+        // placed at the end of the first region, before the fence
+        let cached = palloc.alloc(16).unwrap();
+        palloc.alloc(16).unwrap();
+        palloc.free(cached).unwrap();
+    }
+
+    let dump = heap.dump();
+    let walked = Heap::walk(&dump, base + 512, 512);
+    assert_eq!(walked.problems, []);
+
+    let states: Vec<State> = walked.blocks.iter().map(|block| block.state).collect();
+    use State::*;
+    assert_eq!(states, [Used, Cached, Used, Fence, Free]);
+
+    // the end of the region holding the tail is not known
+    let tail = walked.blocks.last().unwrap();
+    assert_eq!((tail.tail, tail.capacity), (true, None));
+}
+
+#[test]
+fn test_corruption() {
+    let mut heap = Memory([0; 1024]);
+    let mut palloc = Palloc::empty();
+    unsafe { palloc.init_from_slice(&mut heap.0) };
+
+    let allocations = [32, 32, 32, 32].map(|size| unsafe { palloc.alloc(size).unwrap() });
+    unsafe { palloc.free(allocations[1]).unwrap() };
+
+    let word = |alloc: NonNull<u8>, index: u64| unsafe {
+        (header(alloc) as *mut usize).add(index as usize)
+    };
+    let walk = |heap: &Memory| Heap::walk(&heap.dump(), heap.base(), 1024).problems;
+    let problem = |alloc, kind| {
+        vec![Problem {
+            at: header(alloc),
+            kind,
+        }]
+    };
+
+    // the allocation outgrows its block
+    unsafe { *word(allocations[0], 0) += 100 };
+    assert_eq!(
+        walk(&heap),
+        problem(allocations[0], ProblemKind::SizeOverflow)
+    );
+    unsafe { *word(allocations[0], 0) -= 100 };
+
+    // the boundary tag of the free block is wrong
+    unsafe { *word(allocations[2], 0).sub(1) = 0 };
+    assert_eq!(walk(&heap), problem(allocations[1], ProblemKind::BadTag));
+    unsafe { *word(allocations[2], 0).sub(1) = header(allocations[1]) as usize };
+
+    // the chain stops at the link leading backwards
+    unsafe { *word(allocations[2], 1) = header(allocations[0]) as usize };
+    let next = header(allocations[0]);
+    let kind = ProblemKind::LinkNotIncreasing { next };
+    assert_eq!(walk(&heap), problem(allocations[2], kind));
+
+    unsafe { *word(allocations[2], 1) = usize::MAX - 7 };
+    let next = usize::MAX as u64 - 7;
+    assert_eq!(
+        walk(&heap),
+        problem(allocations[2], ProblemKind::InvalidLink { next })
+    );
+    assert_eq!(Heap::walk(&heap.dump(), heap.base(), 1024).blocks.len(), 3);
+}
+
+#[test]
+fn test_target() {
+    // a 32-bit big endian heap of 96 bytes: a free block between
+    // two allocations, of 5 and 4 bytes, followed by the tail
+    let blocks: [&[u32]; 4] = [
+        // 0x1000
+        &[0x8000_0005, 0x1018, 0, 0, 0, 0],
+        // 0x1018, ending with the boundary tag
+        &[0, 0x1030, 0, 0, 0, 0x1018],
+        // 0x1030, flagged as following a free block
+        &[0xa000_0004, 0x1044, 0, 0, 0],
+        // 0x1044
+        &[0; 7],
+    ];
+    let words = blocks.concat();
+    let bytes = words.iter().flat_map(|word| word.to_be_bytes()).collect();
+
+    let target = Target::new(32, Endian::Big).unwrap();
+    let dump = Dump::new(bytes, 0x1000, target);
+    let walked = Heap::walk(&dump, 0x1000, 96);
+    assert_eq!(walked.problems, []);
+
+    let block = |header, size, capacity, state| Block {
+        header,
+        payload: header + 8,
+        size,
+        capacity: Some(capacity),
+        state,
+        prev_free: header == 0x1030,
+        tail: header == 0x1044,
+    };
+    assert_eq!(
+        walked.blocks,
+        [
+            block(0x1000, 5, 16, State::Used),
+            block(0x1018, 0, 16, State::Free),
+            block(0x1030, 4, 12, State::Used),
+            block(0x1044, 0, 20, State::Free),
+        ]
+    );
+
+    assert_eq!(Target::new(24, Endian::Little), None);
+}
@@ -0,0 +1,201 @@
+use crate::{GlobalPalloc, PallocError, UnsafeCellPalloc};
+use core::{alloc::Layout, cell::Cell, ptr::NonNull};
+
+#[cfg(feature = "allocator_api")]
+use core::alloc::{AllocError, Allocator};
+
+/// Bump allocator handing out memory from a single chunk, for data
+/// thrown away all at once.
+///
+/// Each allocation is placed right after the previous one, aligned as
+/// requested: there is no search, no header and nothing to free. Memory
+/// is given back all at once instead, either entirely with
+/// [`reset`](#method.reset) or up to a [`mark`](#method.mark) taken
+/// earlier with [`reset_to`](#method.reset_to). Both take `&mut self`,
+/// so that nothing allocated through `&Arena`, like a `Vec<T, &Arena>`,
+/// can outlive them.
+///
+/// The chunk is either a raw region, see [`from_region`](#method.from_region),
+/// or allocated from a [`GlobalPalloc`] and given back when the arena is
+/// dropped. The allocator is only borrowed through a shared reference, so
+/// that the heap stays usable for other allocations while the arena lives,
+/// see [`FixedPool`](crate::FixedPool).
+pub struct Arena<'a, A: GlobalPalloc = UnsafeCellPalloc> {
+    parent: Option<&'a A>,
+    start: *mut u8,
+    end: usize,
+    /// first free address of the chunk
+    top: Cell<usize>,
+}
+
+/// Position of an [`Arena`] to reset it to, see [`Arena::mark`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Mark(usize);
+
+impl<'a, A: GlobalPalloc> Arena<'a, A> {
+    /// Creates an arena of `size` bytes, allocated from `allocator`.
+    pub fn new(allocator: &'a A, size: usize) -> Result<Self, PallocError> {
+        // global allocators take no empty layouts
+        let layout = Layout::from_size_align(size.max(1), 1).or(Err(PallocError::OutOfMemory))?;
+        let chunk = NonNull::new(unsafe { allocator.alloc(layout) });
+        let chunk = chunk.ok_or(PallocError::OutOfMemory)?;
+
+        Ok(Self::with_chunk(Some(allocator), chunk, size))
+    }
+
+    fn with_chunk(parent: Option<&'a A>, chunk: NonNull<u8>, size: usize) -> Self {
+        Arena {
+            parent,
+            start: chunk.as_ptr(),
+            end: chunk.as_ptr() as usize + size,
+            top: Cell::new(chunk.as_ptr() as usize),
+        }
+    }
+
+    /// Hands out `size` bytes, with no alignment requirements.
+    pub fn alloc(&self, size: usize) -> Result<NonNull<u8>, PallocError> {
+        let layout = Layout::from_size_align(size, 1).or(Err(PallocError::OutOfMemory))?;
+        self.alloc_layout(layout)
+    }
+
+    /// Hands out memory fitting `layout`, right after the previous allocation.
+    /// Returns [`OutOfMemory`](PallocError::OutOfMemory) when the end of the
+    /// chunk is reached.
+    pub fn alloc_layout(&self, layout: Layout) -> Result<NonNull<u8>, PallocError> {
+        let start = self
+            .top
+            .get()
+            .checked_next_multiple_of(layout.align())
+            .ok_or(PallocError::OutOfMemory)?;
+        let end = start
+            .checked_add(layout.size())
+            .filter(|end| *end <= self.end)
+            .ok_or(PallocError::OutOfMemory)?;
+
+        self.top.set(end);
+        Ok(unsafe { NonNull::new_unchecked(start as *mut u8) })
+    }
+
+    /// current position of the arena, to give back everything
+    /// allocated afterwards with [`reset_to`](#method.reset_to)
+    #[inline]
+    pub fn mark(&self) -> Mark {
+        Mark(self.top.get())
+    }
+
+    /// Gives back everything allocated after `mark` was taken. Marks taken
+    /// after the current position, before a previous reset, are ignored.
+    ///
+    /// # Panics
+    /// Panics when `mark` has been taken from another arena.
+    pub fn reset_to(&mut self, mark: Mark) {
+        let start = self.start as usize;
+        assert!(
+            mark.0 >= start && mark.0 <= self.end,
+            "mark taken from another arena"
+        );
+
+        if mark.0 < self.top.get() {
+            self.top.set(mark.0);
+        }
+    }
+
+    /// gives back everything allocated so far
+    pub fn reset(&mut self) {
+        self.top.set(self.start as usize);
+    }
+
+    /// bytes handed out so far, alignment included
+    #[inline]
+    pub fn used(&self) -> usize {
+        self.top.get() - self.start as usize
+    }
+
+    /// size of the chunk
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.end - self.start as usize
+    }
+
+    /// whether `ptr` is the start of the last allocation, `size` bytes long
+    #[cfg(feature = "allocator_api")]
+    fn is_last(&self, ptr: NonNull<u8>, size: usize) -> bool {
+        ptr.as_ptr() as usize + size == self.top.get()
+    }
+}
+
+impl Arena<'static> {
+    /// Creates an arena over a raw region of `size` bytes.
+    ///
+    /// ### Safety
+    /// The whole region must be accessible and free to use,
+    /// for as long as the arena and its allocations are.
+    pub unsafe fn from_region(region: NonNull<u8>, size: usize) -> Self {
+        Self::with_chunk(None, region, size)
+    }
+}
+
+impl<A: GlobalPalloc> Drop for Arena<'_, A> {
+    fn drop(&mut self) {
+        if let (Some(parent), Some(chunk)) = (self.parent, NonNull::new(self.start)) {
+            let _ = unsafe { parent.free(chunk) };
+        }
+    }
+}
+
+/// Deallocating does nothing but for the last allocation, which is given
+/// back right away. The last allocation also grows and shrinks in place.
+#[cfg(feature = "allocator_api")]
+unsafe impl<A: GlobalPalloc> Allocator for Arena<'_, A> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        self.alloc_layout(layout)
+            .map(|ptr| NonNull::slice_from_raw_parts(ptr, layout.size()))
+            .or(Err(AllocError))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, layout: Layout) {
+        if self.is_last(ptr, layout.size()) {
+            self.top.set(ptr.as_ptr() as usize);
+        }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let aligned = (ptr.as_ptr() as usize).is_multiple_of(new_layout.align());
+        let fits = ptr.as_ptr() as usize + new_layout.size() <= self.end;
+
+        if aligned && fits && self.is_last(ptr, old_layout.size()) {
+            self.top.set(ptr.as_ptr() as usize + new_layout.size());
+            return Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()));
+        }
+
+        let grown = self.allocate(new_layout)?;
+        ptr.as_ptr()
+            .copy_to_nonoverlapping(grown.cast().as_ptr(), old_layout.size());
+        Ok(grown)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: Layout,
+        new_layout: Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        if !(ptr.as_ptr() as usize).is_multiple_of(new_layout.align()) {
+            let shrunk = self.allocate(new_layout)?;
+            ptr.as_ptr()
+                .copy_to_nonoverlapping(shrunk.cast().as_ptr(), new_layout.size());
+            return Ok(shrunk);
+        }
+
+        if self.is_last(ptr, old_layout.size()) {
+            self.top.set(ptr.as_ptr() as usize + new_layout.size());
+        }
+
+        Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size()))
+    }
+}
@@ -1,14 +1,217 @@
-use core::alloc::GlobalAlloc;
+use crate::{
+    BuddyPalloc, Fragmentation, Palloc, PallocError, PallocStats, Placement, SegregatedPalloc,
+    TlsfPalloc,
+};
+use core::alloc::{GlobalAlloc, Layout};
 use core::ptr::NonNull;
 
 #[cfg(feature = "allocator_api")]
 use core::alloc::Allocator;
 
+/// Traits every [`GlobalPalloc`] must implement
 #[cfg(feature = "allocator_api")]
 pub trait GlobalPallocConstraint = GlobalAlloc + Allocator;
+/// Traits every [`GlobalPalloc`] must implement
 #[cfg(not(feature = "allocator_api"))]
 pub trait GlobalPallocConstraint = GlobalAlloc;
 
+/// Allocator the global allocators of this crate are built upon, like
+/// [`Palloc`] with any [`Placement`] strategy, [`SegregatedPalloc`],
+/// [`TlsfPalloc`] or [`BuddyPalloc`].
+///
+/// Every method follows the semantics of the [`Palloc`] method
+/// with the same name.
+pub trait PallocBackend: Send {
+    /// empty uninitialized instance, for static initialization.
+    /// See [`Palloc.empty`](crate::Palloc::empty)
+    const EMPTY: Self;
+
+    /// ### Safety
+    /// See [`Palloc.init`](crate::Palloc::init)
+    unsafe fn init(&mut self, bottom: NonNull<u8>, size: usize);
+
+    /// ### Safety
+    /// See [`Palloc.init_from_slice`](crate::Palloc::init_from_slice)
+    unsafe fn init_from_slice(&mut self, heap: &mut [u8]) {
+        let bottom = NonNull::new(heap.as_mut_ptr()).expect("non nullpointed slice");
+        self.init(bottom, heap.len());
+    }
+
+    /// ### Safety
+    /// See [`Palloc.alloc_layout`](crate::Palloc::alloc_layout)
+    unsafe fn alloc_layout(&mut self, layout: Layout) -> Result<NonNull<u8>, PallocError>;
+
+    /// ### Safety
+    /// See [`Palloc.realloc_layout`](crate::Palloc::realloc_layout)
+    unsafe fn realloc_layout(
+        &mut self,
+        alloc: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, PallocError>;
+
+    /// ### Safety
+    /// See [`Palloc.free`](crate::Palloc::free)
+    unsafe fn free(&mut self, alloc: NonNull<u8>) -> Result<(), PallocError>;
+
+    /// See [`Palloc.stats`](crate::Palloc::stats)
+    fn stats(&self) -> PallocStats;
+
+    /// See [`Palloc.fragmentation`](crate::Palloc::fragmentation)
+    fn fragmentation(&self) -> Fragmentation;
+}
+
+impl<P: Placement> PallocBackend for Palloc<P> {
+    const EMPTY: Self = Palloc::new();
+
+    unsafe fn init(&mut self, bottom: NonNull<u8>, size: usize) {
+        Palloc::init(self, bottom, size)
+    }
+
+    unsafe fn alloc_layout(&mut self, layout: Layout) -> Result<NonNull<u8>, PallocError> {
+        Palloc::alloc_layout(self, layout)
+    }
+
+    unsafe fn realloc_layout(
+        &mut self,
+        alloc: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, PallocError> {
+        Palloc::realloc_layout(self, alloc, layout)
+    }
+
+    unsafe fn free(&mut self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        self.global_free(alloc)
+    }
+
+    fn stats(&self) -> PallocStats {
+        Palloc::stats(self)
+    }
+
+    fn fragmentation(&self) -> Fragmentation {
+        Palloc::fragmentation(self)
+    }
+}
+
+impl<P: Placement> PallocBackend for SegregatedPalloc<P> {
+    const EMPTY: Self = SegregatedPalloc::new();
+
+    unsafe fn init(&mut self, bottom: NonNull<u8>, size: usize) {
+        SegregatedPalloc::init(self, bottom, size)
+    }
+
+    unsafe fn alloc_layout(&mut self, layout: Layout) -> Result<NonNull<u8>, PallocError> {
+        SegregatedPalloc::alloc_layout(self, layout)
+    }
+
+    unsafe fn realloc_layout(
+        &mut self,
+        alloc: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, PallocError> {
+        SegregatedPalloc::realloc_layout(self, alloc, layout)
+    }
+
+    unsafe fn free(&mut self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        self.global_free(alloc)
+    }
+
+    fn stats(&self) -> PallocStats {
+        SegregatedPalloc::stats(self)
+    }
+
+    fn fragmentation(&self) -> Fragmentation {
+        SegregatedPalloc::fragmentation(self)
+    }
+}
+
+impl PallocBackend for TlsfPalloc {
+    const EMPTY: Self = TlsfPalloc::empty();
+
+    unsafe fn init(&mut self, bottom: NonNull<u8>, size: usize) {
+        TlsfPalloc::init(self, bottom, size)
+    }
+
+    unsafe fn alloc_layout(&mut self, layout: Layout) -> Result<NonNull<u8>, PallocError> {
+        TlsfPalloc::alloc_layout(self, layout)
+    }
+
+    unsafe fn realloc_layout(
+        &mut self,
+        alloc: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, PallocError> {
+        TlsfPalloc::realloc_layout(self, alloc, layout)
+    }
+
+    unsafe fn free(&mut self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        self.global_free(alloc)
+    }
+
+    fn stats(&self) -> PallocStats {
+        TlsfPalloc::stats(self)
+    }
+
+    fn fragmentation(&self) -> Fragmentation {
+        TlsfPalloc::fragmentation(self)
+    }
+}
+
+impl PallocBackend for BuddyPalloc {
+    const EMPTY: Self = BuddyPalloc::empty();
+
+    unsafe fn init(&mut self, bottom: NonNull<u8>, size: usize) {
+        BuddyPalloc::init(self, bottom, size)
+    }
+
+    unsafe fn alloc_layout(&mut self, layout: Layout) -> Result<NonNull<u8>, PallocError> {
+        BuddyPalloc::alloc_layout(self, layout)
+    }
+
+    unsafe fn realloc_layout(
+        &mut self,
+        alloc: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, PallocError> {
+        BuddyPalloc::realloc_layout(self, alloc, layout)
+    }
+
+    /// freed pointers are always validated, whether the
+    /// `checked_free` feature is enabled or not
+    unsafe fn free(&mut self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        BuddyPalloc::free(self, alloc)
+    }
+
+    fn stats(&self) -> PallocStats {
+        BuddyPalloc::stats(self)
+    }
+
+    fn fragmentation(&self) -> Fragmentation {
+        BuddyPalloc::fragmentation(self)
+    }
+}
+
+/// Called by the global allocators when the heap is found corrupted, with
+/// the header address of the corrupted block, right before reporting the
+/// failure: returning null, an `AllocError`, or panicking on deallocation.
+///
+/// The allocator is not locked anymore when the handler runs, which may
+/// log the address or walk the heap with [`Palloc.check`](crate::Palloc::check)
+/// before halting. See [`HeapCorrupted`](PallocError::HeapCorrupted).
+pub type CorruptionHandler = fn(at: usize);
+
+/// hands the address of the corrupted block over to `handler`,
+/// if any, when `result` reports a corrupted heap
+fn report<T>(
+    handler: Option<CorruptionHandler>,
+    result: Result<T, PallocError>,
+) -> Result<T, PallocError> {
+    if let (Some(handler), Err(PallocError::HeapCorrupted { at })) = (handler, &result) {
+        handler(*at);
+    }
+
+    result
+}
+
 /// Defines what an allocator implementing GlobalAlloc
 /// and Allocator for Palloc should look like.
 /// Struct implementing this are guaranteed to implement GlobalAlloc
@@ -39,6 +242,16 @@ pub trait GlobalPalloc: GlobalPallocConstraint + Sized + Send {
     /// for more informations.
     unsafe fn init_from_slice(&mut self, heap: &mut [u8]);
 
+    /// Deallocates `ptr`, returning the error reported by the allocator
+    /// instead of panicking like `dealloc` and `deallocate` do. Useful to
+    /// recognise a [`DoubleFree`](crate::PallocError::DoubleFree) and log
+    /// its address and size.
+    ///
+    /// ### Safety
+    /// Check out [`Palloc.free`](crate::Palloc::free)
+    /// for more informations.
+    unsafe fn free(&self, ptr: NonNull<u8>) -> Result<(), PallocError>;
+
     /// Creates a [`new`](#tymethod.new) allocator and calls [`init`]
     ///
     /// ### Safety
@@ -1,4 +1,5 @@
-use crate::Palloc;
+use super::{report, CorruptionHandler, GlobalPalloc, PallocBackend};
+use crate::{Fragmentation, Palloc, PallocError, PallocStats};
 use core::{
     alloc::{AllocError, GlobalAlloc},
     ptr::{null_mut, NonNull},
@@ -18,8 +19,12 @@ use core::alloc::Allocator;
 /// SpinPalloc is a generic implementation using the spinlock mutex
 /// technique. It is the only GlobalALloc implemented because it's
 /// the most generic too.
-pub struct SpinPalloc {
-    allocator: Mutex<Palloc, Loop>,
+///
+/// The allocator behind the lock is a first-fit [`Palloc`] by default,
+/// any other [`PallocBackend`] may be used instead.
+pub struct SpinPalloc<A = Palloc> {
+    allocator: Mutex<A, Loop>,
+    on_corruption: Option<CorruptionHandler>,
 }
 
 impl SpinPalloc {
@@ -27,14 +32,59 @@ impl SpinPalloc {
     ///
     /// See [`empty`](crate::Palloc::empty)
     pub const fn empty() -> SpinPalloc {
-        let allocator = Mutex::new(Palloc::empty());
-        SpinPalloc { allocator }
+        SpinPalloc::new()
+    }
+}
+
+impl<A: PallocBackend> SpinPalloc<A> {
+    /// Creates an empty const SpinPalloc uninitialized instance
+    /// using `A` as allocator. See [`empty`](#method.empty)
+    pub const fn new() -> SpinPalloc<A> {
+        let allocator = Mutex::new(A::EMPTY);
+        SpinPalloc {
+            allocator,
+            on_corruption: None,
+        }
+    }
+
+    /// Sets the handler called whenever the heap is found corrupted,
+    /// usable in static initializers. See [`CorruptionHandler`]
+    pub const fn with_corruption_handler(mut self, handler: CorruptionHandler) -> Self {
+        self.on_corruption = Some(handler);
+        self
+    }
+
+    /// runs `operation` on the locked allocator, calling the
+    /// corruption handler once unlocked if needed
+    fn locked<T>(
+        &self,
+        operation: impl FnOnce(&mut A) -> Result<T, PallocError>,
+    ) -> Result<T, PallocError> {
+        let result = operation(&mut self.allocator.lock());
+        report(self.on_corruption, result)
+    }
+
+    /// usage of the heap, see [`Palloc.stats`](crate::Palloc::stats)
+    pub fn stats(&self) -> PallocStats {
+        self.allocator.lock().stats()
+    }
+
+    /// fragmentation of the free memory of the heap,
+    /// see [`Palloc.fragmentation`](crate::Palloc::fragmentation)
+    pub fn fragmentation(&self) -> Fragmentation {
+        self.allocator.lock().fragmentation()
+    }
+}
+
+impl<A: PallocBackend> Default for SpinPalloc<A> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-impl super::GlobalPalloc for SpinPalloc {
+impl<A: PallocBackend> GlobalPalloc for SpinPalloc<A> {
     fn new() -> Self {
-        Self::empty()
+        SpinPalloc::new()
     }
 
     unsafe fn init(&mut self, bottom: NonNull<u8>, size: usize) {
@@ -47,38 +97,93 @@ impl super::GlobalPalloc for SpinPalloc {
     unsafe fn init_from_slice(&mut self, heap: &mut [u8]) {
         self.allocator.lock().init_from_slice(heap)
     }
+
+    unsafe fn free(&self, ptr: NonNull<u8>) -> Result<(), PallocError> {
+        self.locked(|allocator| allocator.free(ptr))
+    }
 }
 
-unsafe impl GlobalAlloc for SpinPalloc {
+unsafe impl<A: PallocBackend> GlobalAlloc for SpinPalloc<A> {
     unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
-        self.allocator
-            .lock()
-            .alloc(layout.size())
+        self.locked(|allocator| allocator.alloc_layout(layout))
             .map(NonNull::as_ptr)
             .unwrap_or(null_mut())
     }
 
     unsafe fn dealloc(&self, ptr: *mut u8, _layout: core::alloc::Layout) {
-        self.allocator
-            .lock()
-            .free(NonNull::new(ptr).expect("pointer for deallocation cannot be null"))
-            .unwrap();
+        let ptr = NonNull::new(ptr).expect("pointer for deallocation cannot be null");
+        if let Err(err) = GlobalPalloc::free(self, ptr) {
+            panic!("palloc: {}", err)
+        }
+    }
+
+    unsafe fn realloc(
+        &self,
+        ptr: *mut u8,
+        layout: core::alloc::Layout,
+        new_size: usize,
+    ) -> *mut u8 {
+        let ptr = NonNull::new(ptr).expect("pointer for reallocation cannot be null");
+        let layout = core::alloc::Layout::from_size_align_unchecked(new_size, layout.align());
+
+        self.locked(|allocator| allocator.realloc_layout(ptr, layout))
+            .map(NonNull::as_ptr)
+            .unwrap_or(null_mut())
     }
 }
 
 #[cfg(feature = "allocator_api")]
-unsafe impl Allocator for SpinPalloc {
+unsafe impl<A: PallocBackend> Allocator for SpinPalloc<A> {
     fn allocate(
         &self,
         layout: core::alloc::Layout,
     ) -> Result<NonNull<[u8]>, core::alloc::AllocError> {
-        match unsafe { self.allocator.lock().alloc(layout.size()) } {
+        match self.locked(|allocator| unsafe { allocator.alloc_layout(layout) }) {
             Err(_) => Err(AllocError),
             Ok(ptr) => Ok(NonNull::slice_from_raw_parts(ptr, layout.size())),
         }
     }
 
     unsafe fn deallocate(&self, ptr: NonNull<u8>, _layout: core::alloc::Layout) {
-        self.allocator.lock().free(ptr).unwrap();
+        if let Err(err) = GlobalPalloc::free(self, ptr) {
+            panic!("palloc: {}", err)
+        }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        _old_layout: core::alloc::Layout,
+        new_layout: core::alloc::Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        match self.locked(|allocator| allocator.realloc_layout(ptr, new_layout)) {
+            Err(_) => Err(AllocError),
+            Ok(ptr) => Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size())),
+        }
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: core::alloc::Layout,
+        new_layout: core::alloc::Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        let grown = self.grow(ptr, old_layout, new_layout)?;
+        let tail = grown.cast::<u8>().as_ptr().add(old_layout.size());
+        tail.write_bytes(0, new_layout.size() - old_layout.size());
+
+        Ok(grown)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        _old_layout: core::alloc::Layout,
+        new_layout: core::alloc::Layout,
+    ) -> Result<NonNull<[u8]>, AllocError> {
+        match self.locked(|allocator| allocator.realloc_layout(ptr, new_layout)) {
+            Err(_) => Err(AllocError),
+            Ok(ptr) => Ok(NonNull::slice_from_raw_parts(ptr, new_layout.size())),
+        }
     }
 }
@@ -1,4 +1,5 @@
-use crate::Palloc;
+use super::{report, CorruptionHandler, GlobalPalloc, PallocBackend};
+use crate::{Fragmentation, Palloc, PallocError, PallocStats};
 use core::{
     alloc::GlobalAlloc,
     cell::UnsafeCell,
@@ -19,22 +20,69 @@ use core::alloc::Allocator;
 ///
 /// For Safety and usage concerns, refer to [`Palloc`](crate::Palloc) or
 /// the crate root documentation
-pub struct UnsafeCellPalloc {
-    allocator: UnsafeCell<Palloc>,
+///
+/// The wrapped allocator is a first-fit [`Palloc`] by default,
+/// any other [`PallocBackend`] may be used instead.
+pub struct UnsafeCellPalloc<A = Palloc> {
+    allocator: UnsafeCell<A>,
+    on_corruption: Option<CorruptionHandler>,
 }
 
 impl UnsafeCellPalloc {
     /// See [`empty`](crate::Palloc::empty)
     pub const fn empty() -> UnsafeCellPalloc {
+        UnsafeCellPalloc::new()
+    }
+}
+
+impl<A: PallocBackend> UnsafeCellPalloc<A> {
+    /// Creates an empty instance using `A` as allocator.
+    /// See [`empty`](#method.empty)
+    pub const fn new() -> UnsafeCellPalloc<A> {
         UnsafeCellPalloc {
-            allocator: UnsafeCell::new(Palloc::empty()),
+            allocator: UnsafeCell::new(A::EMPTY),
+            on_corruption: None,
         }
     }
+
+    /// Sets the handler called whenever the heap is found corrupted,
+    /// usable in static initializers. See [`CorruptionHandler`]
+    pub const fn with_corruption_handler(mut self, handler: CorruptionHandler) -> Self {
+        self.on_corruption = Some(handler);
+        self
+    }
+
+    /// runs `operation` on the allocator, calling the
+    /// corruption handler afterwards if needed
+    unsafe fn run<T>(
+        &self,
+        operation: impl FnOnce(&mut A) -> Result<T, PallocError>,
+    ) -> Result<T, PallocError> {
+        let result = operation(&mut *self.allocator.get());
+        report(self.on_corruption, result)
+    }
+
+    /// usage of the heap, see [`Palloc.stats`](crate::Palloc::stats)
+    pub fn stats(&self) -> PallocStats {
+        unsafe { (*self.allocator.get()).stats() }
+    }
+
+    /// fragmentation of the free memory of the heap,
+    /// see [`Palloc.fragmentation`](crate::Palloc::fragmentation)
+    pub fn fragmentation(&self) -> Fragmentation {
+        unsafe { (*self.allocator.get()).fragmentation() }
+    }
+}
+
+impl<A: PallocBackend> Default for UnsafeCellPalloc<A> {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-impl super::GlobalPalloc for UnsafeCellPalloc {
-    fn new() -> UnsafeCellPalloc {
-        Self::empty()
+impl<A: PallocBackend> GlobalPalloc for UnsafeCellPalloc<A> {
+    fn new() -> UnsafeCellPalloc<A> {
+        UnsafeCellPalloc::new()
     }
 
     unsafe fn init(&mut self, bottom: NonNull<u8>, size: usize) {
@@ -44,35 +92,87 @@ impl super::GlobalPalloc for UnsafeCellPalloc {
     unsafe fn init_from_slice(&mut self, heap: &mut [u8]) {
         self.allocator.get_mut().init_from_slice(heap)
     }
+
+    unsafe fn free(&self, ptr: NonNull<u8>) -> Result<(), PallocError> {
+        self.run(|allocator| allocator.free(ptr))
+    }
 }
 
-unsafe impl GlobalAlloc for UnsafeCellPalloc {
+unsafe impl<A: PallocBackend> GlobalAlloc for UnsafeCellPalloc<A> {
     unsafe fn alloc(&self, layout: core::alloc::Layout) -> *mut u8 {
-        (*self.allocator.get())
-            .alloc(layout.size())
+        self.run(|allocator| allocator.alloc_layout(layout))
             .map(|ptr| ptr.as_ptr())
             .unwrap_or(null_mut())
     }
 
     unsafe fn dealloc(&self, ptr: *mut u8, _layout: core::alloc::Layout) {
-        (*self.allocator.get())
-            .free(NonNull::new(ptr).unwrap())
-            .unwrap();
+        if let Err(err) = GlobalPalloc::free(self, NonNull::new(ptr).unwrap()) {
+            panic!("palloc: {}", err)
+        }
+    }
+
+    unsafe fn realloc(
+        &self,
+        ptr: *mut u8,
+        layout: core::alloc::Layout,
+        new_size: usize,
+    ) -> *mut u8 {
+        let layout = core::alloc::Layout::from_size_align_unchecked(new_size, layout.align());
+        self.run(|allocator| allocator.realloc_layout(NonNull::new(ptr).unwrap(), layout))
+            .map(|ptr| ptr.as_ptr())
+            .unwrap_or(null_mut())
     }
 }
 
 #[cfg(feature = "allocator_api")]
-unsafe impl Allocator for UnsafeCellPalloc {
+unsafe impl<A: PallocBackend> Allocator for UnsafeCellPalloc<A> {
     fn allocate(
         &self,
         layout: core::alloc::Layout,
     ) -> Result<NonNull<[u8]>, core::alloc::AllocError> {
-        unsafe { (*self.allocator.get()).alloc(layout.size()) }
+        unsafe { self.run(|allocator| allocator.alloc_layout(layout)) }
             .map(|ptr| NonNull::slice_from_raw_parts(ptr, layout.size()))
             .or(Err(core::alloc::AllocError))
     }
 
     unsafe fn deallocate(&self, ptr: NonNull<u8>, _layout: core::alloc::Layout) {
-        (*self.allocator.get()).free(ptr).unwrap();
+        if let Err(err) = GlobalPalloc::free(self, ptr) {
+            panic!("palloc: {}", err)
+        }
+    }
+
+    unsafe fn grow(
+        &self,
+        ptr: NonNull<u8>,
+        _old_layout: core::alloc::Layout,
+        new_layout: core::alloc::Layout,
+    ) -> Result<NonNull<[u8]>, core::alloc::AllocError> {
+        self.run(|allocator| allocator.realloc_layout(ptr, new_layout))
+            .map(|ptr| NonNull::slice_from_raw_parts(ptr, new_layout.size()))
+            .or(Err(core::alloc::AllocError))
+    }
+
+    unsafe fn grow_zeroed(
+        &self,
+        ptr: NonNull<u8>,
+        old_layout: core::alloc::Layout,
+        new_layout: core::alloc::Layout,
+    ) -> Result<NonNull<[u8]>, core::alloc::AllocError> {
+        let grown = self.grow(ptr, old_layout, new_layout)?;
+        let tail = grown.cast::<u8>().as_ptr().add(old_layout.size());
+        tail.write_bytes(0, new_layout.size() - old_layout.size());
+
+        Ok(grown)
+    }
+
+    unsafe fn shrink(
+        &self,
+        ptr: NonNull<u8>,
+        _old_layout: core::alloc::Layout,
+        new_layout: core::alloc::Layout,
+    ) -> Result<NonNull<[u8]>, core::alloc::AllocError> {
+        self.run(|allocator| allocator.realloc_layout(ptr, new_layout))
+            .map(|ptr| NonNull::slice_from_raw_parts(ptr, new_layout.size()))
+            .or(Err(core::alloc::AllocError))
     }
 }
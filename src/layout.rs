@@ -0,0 +1,80 @@
+//! Layout of the block headers in memory, for tools reading heaps
+//! from outside of the program, like RAM dumps.
+//!
+//! Every [`Palloc`](crate::Palloc) heap is a chain of blocks, each one made
+//! of a header followed by its payload. The layout described here is only
+//! changed along with [`VERSION`], whatever the version of the crate.
+//!
+//! # Words
+//!
+//! Headers are made of [`HEADER_WORDS`] words of the pointer width of the
+//! target, in its byte order, and always start at an address multiple of
+//! the word size. The payload follows right after the header.
+//!
+//! - word [`SIZE_WORD`] holds the size of the allocation in its low bits,
+//!   and the flags in its [`FLAG_BITS`] most significant bits. The size is
+//!   kept once the block is freed.
+//! - word [`NEXT_WORD`] holds the address of the next header, or zero for
+//!   the last block of the chain: the tail.
+//!
+//! The capacity of a block is the distance from the end of its header to
+//! the next one. Allocations fit in it, while free blocks have at least
+//! [`MIN_CAPACITY_WORDS`] words of capacity.
+//!
+//! # Flags
+//!
+//! Flags are numbered from the most significant bit of the size word
+//! down, see [`flag`]:
+//!
+//! - [`ALLOCATED_BIT`]: the block is in use.
+//! - [`CACHED_BIT`]: set along with the allocated flag, the block has been
+//!   released but kept aside by an allocator built on top of the chain, like
+//!   [`SegregatedPalloc`](crate::SegregatedPalloc). Its memory is free.
+//! - [`PREV_FREE_BIT`]: the preceding block is free, and the last word
+//!   before this header, the boundary tag, holds the address of its header.
+//! - [`FENCE_BIT`]: set along with the allocated flag on the block closing
+//!   a region of the heap. Its next header is the first one of the following
+//!   region, which may lie anywhere in memory. Fences have a size of zero.
+//!
+//! # Chain
+//!
+//! The chain starts at the bottom of the first region, aligned up to the
+//! word size, and links are increasing within a region. Free blocks are
+//! always merged with their free neighbours, and linked together in address
+//! order by the first two words of their payload: [`PREV_LINK_WORD`] and
+//! [`NEXT_LINK_WORD`], zero at both ends of the list. The tail is free but
+//! kept out of the list, its capacity runs up to the end of the last region.
+
+/// version of the layout, bumped on any change to it
+pub const VERSION: u32 = 1;
+
+/// words of a block header
+pub const HEADER_WORDS: usize = 2;
+/// header word holding the size of the allocation along with the flags
+pub const SIZE_WORD: usize = 0;
+/// header word holding the address of the next header, zero for the tail
+pub const NEXT_WORD: usize = 1;
+
+/// number of most significant bits of the size word taken by the flags
+pub const FLAG_BITS: u32 = 4;
+/// flag of the blocks in use
+pub const ALLOCATED_BIT: u32 = 0;
+/// flag of the allocated blocks kept aside by an allocator built on top
+pub const CACHED_BIT: u32 = 1;
+/// flag of the blocks whose preceding block is free
+pub const PREV_FREE_BIT: u32 = 2;
+/// flag of the allocated blocks linking a region to the next one
+pub const FENCE_BIT: u32 = 3;
+
+/// smallest capacity of a block, in words
+pub const MIN_CAPACITY_WORDS: usize = 3;
+/// payload word of a free block holding the address of the previous free header
+pub const PREV_LINK_WORD: usize = 0;
+/// payload word of a free block holding the address of the next free header
+pub const NEXT_LINK_WORD: usize = 1;
+
+/// mask of the flag `bit` in the size word of a target
+/// whose pointers are `word_bits` wide
+pub const fn flag(bit: u32, word_bits: u32) -> u64 {
+    1 << (word_bits - 1 - bit)
+}
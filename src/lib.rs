@@ -1,10 +1,7 @@
 #![no_std]
 #![warn(missing_docs)]
 #![deny(rustdoc::broken_intra_doc_links)]
-#![cfg_attr(
-    feature = "allocator_api",
-    feature(allocator_api, nonnull_slice_from_raw_parts)
-)]
+#![cfg_attr(feature = "allocator_api", feature(allocator_api))]
 #![feature(trait_alias)]
 
 //! Portable allocator designed for baremetal systems
@@ -14,18 +11,34 @@
 //! baremetal project, also available on my github.
 //!
 //! This allocator is not speed-oriented, while still being relatively efficent.
-//! Allocations have a 2*usize overhead
+//! Allocations have a 2*usize overhead: a header holding their size along with
+//! a few flags, and the address of the next block. Payloads are rounded up to a
+//! multiple of usize, and take at least 3*usize, so that a freed block can hold
+//! the links to its free neighbours along with its boundary tag: an allocation
+//! of a single byte takes 5*usize of heap. See the [`layout`] module.
 //!
 //! A practical example of how to use this crate as a global allocator is available
 //! in the [README.md](https://github.com/BRA1L0R/palloc) file
 
 /// allocator module
 pub mod palloc;
-pub use crate::palloc::{Palloc, PallocError};
+pub use crate::palloc::{
+    BestFit, BlockInfo, Blocks, BuddyPalloc, FirstFit, Fragmentation, HeapError, HeapErrorKind,
+    MoreCore, NextFit, Palloc, PallocError, PallocStats, Placement, SegregatedPalloc, TlsfPalloc,
+    WorstFit,
+};
+
+pub mod layout;
 
 /// GlobalAlloc implementations
 pub mod global;
 pub use crate::global::*;
 
+mod arena;
+pub use crate::arena::{Arena, Mark};
+
+mod pool;
+pub use crate::pool::{FixedPool, Pool};
+
 #[cfg(test)]
 mod test;
@@ -1,8 +1,52 @@
-use crate::PallocError;
-use core::{mem::size_of, ptr::NonNull};
+use super::free_list::{FreeLinks, FreeList};
+use crate::{layout, PallocError};
+use core::{
+    mem::{align_of, size_of},
+    ptr::NonNull,
+};
 
 pub type BlockRef = &'static mut MemoryBlock;
 
+/// alignment of every block header and, consequently, of every heap
+pub const ALIGN: usize = align_of::<MemoryBlock>();
+
+/// set in the allocation word of every block in use. The remaining
+/// bits hold the requested size, which may very well be zero.
+const ALLOCATED: usize = flag(layout::ALLOCATED_BIT);
+/// set along with [`ALLOCATED`] on blocks that have been released but
+/// are kept aside by an allocator built on top of the block chain.
+const CACHED: usize = flag(layout::CACHED_BIT);
+/// set on blocks following a free one, whose header address is then
+/// stored in its last word: the boundary tag.
+const PREV_FREE: usize = flag(layout::PREV_FREE_BIT);
+/// set along with [`ALLOCATED`] on the block closing a region of the
+/// heap, whose next block is the first one of the following region.
+const FENCE: usize = flag(layout::FENCE_BIT);
+
+const FLAGS: usize = ALLOCATED | CACHED | PREV_FREE | FENCE;
+/// largest size that can be stored next to the flags
+pub const MAX_SIZE: usize = !FLAGS;
+
+/// smallest payload of a block, so that it can hold the
+/// free list links and the boundary tag once freed
+pub const MIN_CAPACITY: usize = layout::MIN_CAPACITY_WORDS * size_of::<usize>();
+
+const fn flag(bit: u32) -> usize {
+    layout::flag(bit, usize::BITS) as usize
+}
+
+// the documented layout, see the layout module
+const _: () = {
+    assert!(FLAGS.count_ones() == layout::FLAG_BITS);
+    assert!(size_of::<MemoryBlock>() == layout::HEADER_WORDS * size_of::<usize>());
+    assert!(
+        core::mem::offset_of!(MemoryBlock, allocation) == layout::SIZE_WORD * size_of::<usize>()
+    );
+    assert!(core::mem::offset_of!(MemoryBlock, next) == layout::NEXT_WORD * size_of::<usize>());
+    assert!(core::mem::offset_of!(FreeLinks, prev) == layout::PREV_LINK_WORD * size_of::<usize>());
+    assert!(core::mem::offset_of!(FreeLinks, next) == layout::NEXT_LINK_WORD * size_of::<usize>());
+};
+
 #[derive(Default)]
 #[repr(C)]
 pub struct MemoryBlock {
@@ -24,17 +68,52 @@ impl MemoryBlock {
         block
     }
 
-    pub fn allocate(&mut self, size: usize) -> Result<*mut u8, PallocError> {
-        match (self.allocation, self.max_size()) {
-            (0, Some(maxsize)) if maxsize < size => Err(PallocError::NoBlockSpace),
-            (0, _) => {
-                self.allocation = size;
-                Ok(self.heap())
+    /// Allocates `size` bytes on a free block, taking it off `list`.
+    ///
+    /// # Safety
+    /// A linked free block must be in `list`.
+    pub unsafe fn allocate(
+        &mut self,
+        size: usize,
+        list: &FreeList,
+    ) -> Result<*mut u8, PallocError> {
+        match (self.is_allocated(), self.max_size()) {
+            (true, _) => Err(PallocError::AlreadyAllocated),
+            (false, Some(maxsize)) if maxsize < size => Err(PallocError::NoBlockSpace),
+            (false, maxsize) => {
+                if maxsize.is_some() {
+                    list.remove(self);
+                }
+
+                Ok(self.claim(size))
             }
-            (_, _) => Err(PallocError::AlreadyAllocated),
         }
     }
 
+    /// marks a free block as allocated with `size` bytes, leaving
+    /// whatever list it belongs to up to the caller
+    pub fn claim(&mut self, size: usize) -> *mut u8 {
+        debug_assert!(!self.is_allocated());
+        self.allocation = size | ALLOCATED | (self.allocation & PREV_FREE);
+        self.heap()
+    }
+
+    /// Turns a free block into the fence closing a region: a block of
+    /// size zero, allocated for good, followed by the first block of
+    /// the next region, wherever it lies.
+    pub fn fence(&mut self, next: BlockRef) {
+        debug_assert!(!self.is_allocated());
+        self.allocation = ALLOCATED | FENCE | (self.allocation & PREV_FREE);
+        self.next = Some(next);
+    }
+
+    /// changes the size of an allocated block, capacity
+    /// must be checked beforehand.
+    pub fn resize(&mut self, size: usize) {
+        debug_assert!(self.is_allocated());
+        self.allocation = size | ALLOCATED | (self.allocation & PREV_FREE);
+    }
+
     /// # Safety
     pub unsafe fn insert_default(&mut self, address: NonNull<MemoryBlock>) {
         let inserted = Self::default_from_ptr(address);
@@ -42,42 +121,144 @@ impl MemoryBlock {
         self.next = Some(inserted);
     }
 
-    pub fn merge(&mut self, target_size: usize) -> Result<(), PallocError> {
+    /// Splits the leading `padding` bytes of the heap off into a free block
+    /// and returns the block that now follows them, put in `list` along with
+    /// the padding. A padding of zero leaves the block untouched.
+    ///
+    /// # Safety
+    /// `padding` must come from [`padding`](Self::padding) and fit
+    /// within the capacity of the free block.
+    pub unsafe fn split_padding(&mut self, padding: usize, list: &FreeList) -> &mut MemoryBlock {
+        if padding == 0 {
+            return self;
+        }
+
+        let header = self.heap() as usize + padding - size_of::<Self>();
+        self.insert_default(NonNull::new_unchecked(header as *mut _));
+
+        let aligned = self.next.as_deref_mut().unwrap() as *mut MemoryBlock;
+        match (*aligned).is_linked() {
+            true => {
+                self.tag();
+                list.insert(&mut *aligned);
+            }
+            false => {
+                // the padding is no longer the tail
+                list.set_tail(&mut *aligned);
+                list.insert(self);
+            }
+        }
+
+        &mut *aligned
+    }
+
+    /// Bytes that must be skipped from the start of the heap so that the
+    /// returned allocation is aligned to `align`. When non-zero, there is
+    /// always enough room left for the header of the aligned block and
+    /// the padding is a block of its own.
+    pub fn padding(&self, align: usize) -> usize {
+        let heap = self.heap() as usize;
+        match heap % align {
+            0 => 0,
+            _ => align_up(heap + MIN_CAPACITY + size_of::<Self>(), align) - heap,
+        }
+    }
+
+    /// Absorbs the free blocks following this one, taking them off `list`,
+    /// until its capacity reaches `target_size`. Absorbing the tail makes
+    /// this block the tail of `list`.
+    ///
+    /// # Safety
+    /// A free block must be in `list`, unless it is the tail.
+    pub unsafe fn merge(&mut self, target_size: usize, list: &FreeList) -> Result<(), PallocError> {
+        let merged = self.absorb(target_size, list);
+        if !self.is_allocated() && self.is_linked() {
+            self.tag();
+        }
+
+        merged
+    }
+
+    unsafe fn absorb(&mut self, target_size: usize, list: &FreeList) -> Result<(), PallocError> {
         while let Some(maxsize) = self.max_size() {
             if maxsize >= target_size {
                 break;
             }
 
-            let next_block = self.next.as_mut().unwrap();
-            match next_block.allocation {
-                0 => self.next = next_block.next.take(),
-                _ => return Err(PallocError::NoBlockSpace),
+            let next_block = self.next.as_deref_mut().unwrap() as *mut MemoryBlock;
+            let next_block = &mut *next_block;
+            if next_block.is_allocated() {
+                return Err(PallocError::NoBlockSpace);
+            }
+
+            match next_block.is_linked() {
+                true => {
+                    let rover = core::ptr::eq(list.rover(), next_block);
+                    list.remove(next_block);
+                    if rover && !self.is_allocated() {
+                        list.set_rover(self);
+                    }
+                }
+                false => {
+                    if !self.is_allocated() {
+                        list.remove(self);
+                    }
+                    list.set_tail(self);
+                }
             }
+
+            self.next = next_block.next.take();
         }
 
         Ok(())
     }
 
-    pub unsafe fn segment(&mut self) -> Result<(), PallocError> {
+    /// absorbs the following block, leaving whatever list it belongs to up to the caller
+    ///
+    /// # Safety
+    /// The block must be linked and the following block free.
+    pub unsafe fn absorb_next(&mut self) {
+        let next = self.next.as_deref_mut().unwrap() as *mut MemoryBlock;
+        debug_assert!(!(*next).is_allocated());
+        self.next = (*next).next.take();
+    }
+
+    /// absorbs every free block following this one, tail included
+    ///
+    /// # Safety
+    /// See [`merge`](Self::merge)
+    pub unsafe fn coalesce(&mut self, list: &FreeList) {
+        let _ = self.merge(usize::MAX, list);
+    }
+
+    /// Splits the memory left unused by the allocation off into a new
+    /// free block, merged with any free block following it.
+    ///
+    /// # Safety
+    /// The free blocks of the chain must be in `list`.
+    pub unsafe fn segment(&mut self, list: &FreeList) -> Result<(), PallocError> {
         let maxsize = self.max_size().ok_or(PallocError::SegmentingTail)?;
-        let allocated = match self.allocation {
-            0 => Err(PallocError::NotAllocated),
-            n => Ok(n),
-        }?;
-
-        if (allocated + size_of::<Self>()) < maxsize {
-            let newblock = NonNull::new_unchecked((self.heap() as usize + allocated) as *mut _);
-            self.insert_default(newblock);
+        if !self.is_allocated() {
+            return Err(PallocError::NotAllocated);
+        }
+
+        let end = self.allocation_end();
+        if end + size_of::<Self>() + MIN_CAPACITY <= self.heap() as usize + maxsize {
+            self.insert_default(NonNull::new_unchecked(end as *mut _));
+
+            let newblock = self.next.as_mut().unwrap();
+            list.insert(newblock);
+            newblock.coalesce(list);
         }
 
         Ok(())
     }
 
     pub fn dealloc(&mut self) -> Result<(), PallocError> {
-        match self.allocation {
-            0 => Err(PallocError::NotAllocated),
-            _ => {
-                self.allocation = 0;
+        match self.is_allocated() {
+            false => Err(PallocError::NotAllocated),
+            true => {
+                self.allocation &= !(ALLOCATED | CACHED);
                 Ok(())
             }
         }
@@ -89,24 +270,86 @@ impl MemoryBlock {
             .map(|next| (*next as *const MemoryBlock as usize) - self.heap() as usize)
     }
 
+    /// Places a new tail right after the allocation.
+    ///
     /// # Safety
-    pub unsafe fn link_default(&mut self) {
-        if self.allocation == 0 {
+    pub unsafe fn link_default(&mut self, list: &FreeList) {
+        if !self.is_allocated() {
             panic!("cannot without being allocated")
         }
 
-        let new_link = Self::default_from_ptr(NonNull::new_unchecked(
-            (self.heap() as usize + self.allocation) as *mut _,
-        ));
+        let new_link =
+            Self::default_from_ptr(NonNull::new_unchecked(self.allocation_end() as *mut _));
 
+        list.set_tail(new_link);
         self.next = Some(new_link);
     }
 
+    /// Address of the next header, zero for the tail. The link is read
+    /// as a raw word, so that it can be checked before being followed
+    /// when the header may be corrupted.
+    #[inline]
+    pub fn next_addr(&self) -> usize {
+        // `Option<&mut T>` is guaranteed to be a nullable pointer
+        unsafe { core::ptr::addr_of!(self.next).cast::<usize>().read() }
+    }
+
+    #[inline]
+    pub fn next(&self) -> Option<&MemoryBlock> {
+        self.next.as_deref()
+    }
+
+    #[inline]
+    pub fn next_mut(&mut self) -> Option<&mut MemoryBlock> {
+        self.next.as_deref_mut()
+    }
+
+    /// Writes the boundary tag of a free block, right before the
+    /// following header, and flags the following block.
+    ///
+    /// # Safety
+    /// The block must be free and linked.
+    pub unsafe fn tag(&mut self) {
+        let address = self as *mut MemoryBlock;
+        let next = self.next.as_deref_mut().unwrap();
+
+        next.allocation |= PREV_FREE;
+        *((next.address() - size_of::<usize>()) as *mut *mut MemoryBlock) = address;
+    }
+
+    /// clears the flag set by [`tag`](Self::tag) on the following block
+    pub fn untag(&mut self) {
+        if let Some(next) = self.next.as_deref_mut() {
+            next.allocation &= !PREV_FREE;
+        }
+    }
+
+    /// Free block preceding this one, found through its boundary tag.
+    ///
+    /// # Safety
+    pub unsafe fn previous_free(&self) -> Option<BlockRef> {
+        match self.is_prev_free() {
+            false => None,
+            true => Some(&mut **((self.address() - size_of::<usize>()) as *mut *mut MemoryBlock)),
+        }
+    }
+
+    #[inline]
+    pub fn address(&self) -> usize {
+        self as *const MemoryBlock as usize
+    }
+
     pub fn heap(&self) -> *mut u8 {
         let self_addr = self as *const MemoryBlock as usize;
         (self_addr + size_of::<Self>()) as _
     }
 
+    /// first address after the allocation where a header can be placed,
+    /// leaving at least [`MIN_CAPACITY`] bytes to the block
+    fn allocation_end(&self) -> usize {
+        self.heap() as usize + capacity_for(self.size())
+    }
+
     // pub fn top(&'static mut self) -> *mut MemoryBlock {
     //     let selfptr = self as *mut MemoryBlock;
     //     self.next.as_mut().map_or(selfptr, |block| block.top())
@@ -118,13 +361,45 @@ impl MemoryBlock {
             .map(|mut ptr| ptr.as_mut())
     }
 
+    #[cfg(test)]
     pub fn iter_mut(&'static mut self) -> BlockIterator {
         BlockIterator::new(self)
     }
 
+    /// size of the allocation, kept after the block is freed
+    #[inline]
+    pub fn size(&self) -> usize {
+        self.allocation & !FLAGS
+    }
+
+    /// Marks an allocated block as cached, or in use again. Resizing
+    /// the block marks it as in use as well.
+    pub fn set_cached(&mut self, cached: bool) {
+        debug_assert!(self.is_allocated());
+        match cached {
+            true => self.allocation |= CACHED,
+            false => self.allocation &= !CACHED,
+        }
+    }
+
+    #[inline]
+    pub fn is_cached(&self) -> bool {
+        self.allocation & CACHED != 0
+    }
+
+    #[inline]
+    pub fn is_prev_free(&self) -> bool {
+        self.allocation & PREV_FREE != 0
+    }
+
+    #[inline]
+    pub fn is_fence(&self) -> bool {
+        self.allocation & FENCE != 0
+    }
+
     #[inline]
     pub fn is_allocated(&self) -> bool {
-        self.allocation != 0
+        self.allocation & ALLOCATED != 0
     }
 
     #[inline]
@@ -133,6 +408,15 @@ impl MemoryBlock {
     }
 }
 
+pub fn align_up(addr: usize, align: usize) -> usize {
+    (addr + align - 1) & !(align - 1)
+}
+
+/// capacity of a block holding exactly `size` bytes
+pub fn capacity_for(size: usize) -> usize {
+    align_up(size, ALIGN).max(MIN_CAPACITY)
+}
+
 pub struct BlockIterator {
     current: Option<*mut MemoryBlock>,
 }
@@ -153,12 +437,12 @@ impl Iterator for BlockIterator {
     type Item = BlockRef;
 
     fn next(&mut self) -> Option<Self::Item> {
-        unsafe { self.current_mut() }.map(|current| {
-            self.current = current
-                .next
-                .as_mut()
-                .map(|block| *block as *mut MemoryBlock);
-            current
-        })
+        let current = unsafe { self.current_mut() }?;
+        self.current = current
+            .next
+            .as_deref_mut()
+            .map(|block| block as *mut MemoryBlock);
+
+        Some(current)
     }
 }
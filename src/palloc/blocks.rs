@@ -0,0 +1,64 @@
+use super::block::MemoryBlock;
+use core::{marker::PhantomData, mem::size_of};
+
+/// Layout of a block of the heap, see [`Palloc.blocks`](crate::Palloc::blocks).
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct BlockInfo {
+    /// address of the payload, the one handed out for allocated blocks
+    pub addr: usize,
+    /// address of the block header, right before the payload
+    pub header_addr: usize,
+    /// bytes requested by the allocation, zero for free blocks
+    pub size: usize,
+    /// bytes available to the payload, up to the next header
+    pub capacity: usize,
+    /// whether the block is in use
+    pub allocated: bool,
+}
+
+/// Iterator over the blocks of a heap, in the order of the chain.
+/// See [`Palloc.blocks`](crate::Palloc::blocks).
+pub struct Blocks<'a> {
+    current: *const MemoryBlock,
+    /// end of the region holding the tail
+    top: usize,
+    heap: PhantomData<&'a MemoryBlock>,
+}
+
+impl Blocks<'_> {
+    pub(super) fn new(origin: *const MemoryBlock, top: usize) -> Self {
+        Self {
+            current: origin,
+            top,
+            heap: PhantomData,
+        }
+    }
+}
+
+impl Iterator for Blocks<'_> {
+    type Item = BlockInfo;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let mut block = unsafe { self.current.as_ref() }?;
+
+        // fences only link regions together
+        while block.is_fence() {
+            block = block.next().unwrap();
+        }
+
+        self.current = block
+            .next()
+            .map_or(core::ptr::null(), |next| next as *const MemoryBlock);
+
+        let allocated = block.is_allocated() && !block.is_cached();
+        let addr = block.heap() as usize;
+
+        Some(BlockInfo {
+            addr,
+            header_addr: addr - size_of::<MemoryBlock>(),
+            size: if allocated { block.size() } else { 0 },
+            capacity: block.max_size().unwrap_or_else(|| self.top - addr),
+            allocated,
+        })
+    }
+}
@@ -0,0 +1,357 @@
+use super::{stats::Usage, Fragmentation, PallocError, PallocStats};
+use core::{
+    alloc::Layout,
+    mem::size_of,
+    ptr::{copy_nonoverlapping, null_mut, NonNull},
+};
+
+/// log2 of the smallest block, large enough for the free list links
+const MIN_ORDER: u32 = (4 * size_of::<usize>()).trailing_zeros();
+const MIN_BLOCK: usize = 1 << MIN_ORDER;
+const ORDERS: usize = usize::BITS as usize;
+
+/// set in the order table entry of allocated blocks, along with their order.
+/// Entries of free blocks hold their order alone, other entries are zero.
+const ALLOCATED: u8 = 0x80;
+
+/// links between the free blocks of the same order, stored in their first words
+#[derive(Clone, Copy)]
+#[repr(C)]
+struct FreeBuddy {
+    prev: *mut FreeBuddy,
+    next: *mut FreeBuddy,
+}
+
+/// Buddy system allocator, handing out power of two sized blocks
+/// aligned to their own size.
+///
+/// Allocations are rounded up to the next power of two, at least 4 words,
+/// and to their alignment. A block is split in halves, its buddies, until
+/// it is as small as the allocation; freeing a block merges it back with
+/// its buddy whenever the latter is free, up to the largest block.
+/// Splitting and merging both take a number of steps logarithmic in the
+/// size of the heap, at the cost of the memory lost in rounding up.
+///
+/// Blocks carry no header: the order of each one is kept in a table of
+/// one byte every 4 words, placed at the bottom of the heap. Freed pointers
+/// are always validated against this table, so that freeing anything but
+/// the start of an allocation returns an error instead of corrupting the heap.
+///
+/// # Safety
+/// See [`Palloc`](crate::Palloc)
+pub struct BuddyPalloc {
+    /// first address of the blocks, right after the order table
+    base: usize,
+    end: usize,
+    table: *mut u8,
+    /// free blocks of every order
+    lists: [*mut FreeBuddy; ORDERS],
+    usage: Usage,
+}
+
+impl BuddyPalloc {
+    /// smallest block handed out, and so smallest alignment of every allocation
+    pub const MIN_BLOCK: usize = MIN_BLOCK;
+
+    /// creates an empty allocator, see [`Palloc.empty`](crate::Palloc::empty)
+    pub const fn empty() -> BuddyPalloc {
+        BuddyPalloc {
+            base: 0,
+            end: 0,
+            table: null_mut(),
+            lists: [null_mut(); ORDERS],
+            usage: Usage::new(),
+        }
+    }
+
+    /// Initializes the allocator with a pointer to a free heap region
+    /// and a size which defines the upper bound of the same.
+    ///
+    /// The order table is placed at the bottom of the region and the rest
+    /// is split into the largest blocks aligned to their own size, so that
+    /// a region aligned to a power of two makes up a single block.
+    ///
+    /// ### Safety
+    /// See [`Palloc.init`](crate::Palloc::init). The region must also fit
+    /// the table along with the smallest block.
+    pub unsafe fn init(&mut self, bottom: NonNull<u8>, size: usize) {
+        let start = bottom.as_ptr() as usize;
+        let end = (start + size) & !(MIN_BLOCK - 1);
+        let table_len = size / MIN_BLOCK;
+        let base = (start + table_len + MIN_BLOCK - 1) & !(MIN_BLOCK - 1);
+
+        assert!(
+            base < end,
+            "heap region must fit the order table and a block"
+        );
+
+        *self = Self::empty();
+        self.base = base;
+        self.end = end;
+        self.table = bottom.as_ptr();
+        self.table.write_bytes(0, table_len);
+
+        let mut block = base;
+        while block < end {
+            let aligned = block.trailing_zeros().min(usize::BITS - 1);
+            let fitting = usize::BITS - 1 - (end - block).leading_zeros();
+            let order = aligned.min(fitting);
+
+            self.push(block, order);
+            block += 1 << order;
+        }
+    }
+
+    /// Initializes heap from a memory slice. See [`init`](#method.init) for more informations.
+    ///
+    /// ### Safety
+    /// See [`init`](#method.init)
+    pub unsafe fn init_from_slice(&mut self, heap: &mut [u8]) {
+        let bottom = NonNull::new(heap.as_mut_ptr()).expect("non nullpointed slice");
+        self.init(bottom, heap.len());
+    }
+
+    /// ### Safety
+    /// See [`Palloc.alloc`](crate::Palloc::alloc)
+    pub unsafe fn alloc(&mut self, size: usize) -> Result<NonNull<u8>, PallocError> {
+        let layout = Layout::from_size_align(size, 1).or(Err(PallocError::OutOfMemory))?;
+        self.alloc_layout(layout)
+    }
+
+    /// Creates a new allocation fitting `layout`, on the smallest free block
+    /// large enough, split in halves as many times as possible. The returned
+    /// pointer is aligned to the size of the block.
+    ///
+    /// ### Safety
+    /// See [`Palloc.alloc_layout`](crate::Palloc::alloc_layout)
+    pub unsafe fn alloc_layout(&mut self, layout: Layout) -> Result<NonNull<u8>, PallocError> {
+        let order = order_of(layout).ok_or(PallocError::OutOfMemory)?;
+        let mut current = (order..ORDERS as u32)
+            .find(|order| !self.lists[*order as usize].is_null())
+            .ok_or(PallocError::OutOfMemory)?;
+
+        let block = self.lists[current as usize] as usize;
+        self.remove(block, current);
+
+        while current > order {
+            current -= 1;
+            self.push(block + (1 << current), current);
+        }
+
+        *self.entry(block) = order as u8 | ALLOCATED;
+        self.usage.add(1 << order);
+        Ok(NonNull::new_unchecked(block as *mut u8))
+    }
+
+    /// Resizes the allocation at `alloc` to fit `layout`, preserving its content
+    /// up to the smaller of the two sizes. Shrinking splits the block in place,
+    /// giving back its upper halves, while growing always moves the allocation.
+    ///
+    /// ### Safety
+    /// See [`Palloc.realloc_layout`](crate::Palloc::realloc_layout)
+    pub unsafe fn realloc_layout(
+        &mut self,
+        alloc: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, PallocError> {
+        let block = alloc.as_ptr() as usize;
+        let mut current = self.allocated_order(block)?;
+        let order = order_of(layout).ok_or(PallocError::OutOfMemory)?;
+
+        if order <= current {
+            self.usage.sub((1 << current) - (1 << order));
+            while current > order {
+                current -= 1;
+                self.push(block + (1 << current), current);
+            }
+
+            *self.entry(block) = order as u8 | ALLOCATED;
+            return Ok(alloc);
+        }
+
+        let moved = self.alloc_layout(layout)?;
+        copy_nonoverlapping(alloc.as_ptr(), moved.as_ptr(), 1 << current);
+        self.free(alloc)?;
+
+        Ok(moved)
+    }
+
+    /// Deallocates memory at a given pointer location, merging the block with
+    /// its buddy for as long as the latter is free.
+    ///
+    /// Pointers outside of the heap return [`ForeignPointer`](PallocError::ForeignPointer),
+    /// pointers within it not pointing to the start of an allocation
+    /// [`InvalidPointer`](PallocError::InvalidPointer) and freed ones
+    /// [`DoubleFree`](PallocError::DoubleFree), along with the size of their block.
+    ///
+    /// ### Safety
+    /// Any pointer is accepted, but the heap itself must not be corrupted.
+    pub unsafe fn free(&mut self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        let mut block = alloc.as_ptr() as usize;
+        let mut order = self.allocated_order(block)?;
+        *self.entry(block) = order as u8;
+        self.usage.sub(1 << order);
+
+        while order < ORDERS as u32 - 1 {
+            let buddy = block ^ (1 << order);
+            let in_heap = buddy >= self.base && buddy < self.end;
+            if !in_heap || *self.entry(buddy) != order as u8 {
+                break;
+            }
+
+            // the upper half now lies within the merged block
+            self.remove(buddy, order);
+            *self.entry(block.max(buddy)) = 0;
+            block = block.min(buddy);
+            order += 1;
+        }
+
+        self.push(block, order);
+        Ok(())
+    }
+
+    /// Walks every block of the heap to report its usage, see
+    /// [`Palloc.stats`](crate::Palloc::stats). The size requested for each
+    /// allocation is not kept, so that whole blocks are reported as used,
+    /// while the order table is accounted as overhead.
+    pub fn stats(&self) -> PallocStats {
+        let mut stats = PallocStats {
+            peak: self.usage.peak(),
+            overhead: self.base.saturating_sub(self.table as usize),
+            ..Default::default()
+        };
+
+        let mut block = self.base;
+        while block < self.end {
+            let entry = unsafe { *self.entry(block) };
+            let size = 1 << (entry & !ALLOCATED);
+
+            match entry & ALLOCATED {
+                0 => {
+                    stats.free += size;
+                    stats.free_blocks += 1;
+                    stats.largest_free = stats.largest_free.max(size);
+                }
+                _ => {
+                    stats.used += size;
+                    stats.allocated_blocks += 1;
+                }
+            }
+
+            block += size;
+        }
+
+        stats
+    }
+
+    /// Walks every block of the heap to report how fragmented its free memory
+    /// is, see [`Palloc.fragmentation`](crate::Palloc::fragmentation).
+    pub fn fragmentation(&self) -> Fragmentation {
+        let mut fragmentation = Fragmentation::new();
+
+        let mut block = self.base;
+        while block < self.end {
+            let entry = unsafe { *self.entry(block) };
+            let size = 1 << (entry & !ALLOCATED);
+            if entry & ALLOCATED == 0 {
+                fragmentation.add(size);
+            }
+
+            block += size;
+        }
+
+        fragmentation
+    }
+
+    /// order of the allocated block starting at `block`
+    unsafe fn allocated_order(&self, block: usize) -> Result<u32, PallocError> {
+        if block < self.base || block >= self.end {
+            return Err(PallocError::ForeignPointer);
+        }
+        if !block.is_multiple_of(MIN_BLOCK) {
+            return Err(PallocError::InvalidPointer);
+        }
+
+        match *self.entry(block) {
+            0 => Err(PallocError::InvalidPointer),
+            entry if entry & ALLOCATED == 0 => Err(PallocError::DoubleFree {
+                addr: block,
+                size: 1 << entry,
+            }),
+            entry => Ok((entry & !ALLOCATED) as u32),
+        }
+    }
+
+    /// order table entry of the block starting at `block`
+    unsafe fn entry(&self, block: usize) -> *mut u8 {
+        self.table.add((block - self.base) / MIN_BLOCK)
+    }
+
+    /// pushes a free block on the list of its order
+    unsafe fn push(&mut self, block: usize, order: u32) {
+        let head = self.lists[order as usize];
+        let links = block as *mut FreeBuddy;
+
+        *links = FreeBuddy {
+            prev: null_mut(),
+            next: head,
+        };
+        if !head.is_null() {
+            (*head).prev = links;
+        }
+
+        self.lists[order as usize] = links;
+        *self.entry(block) = order as u8;
+    }
+
+    /// takes a free block off the list of its order. Its table
+    /// entry is left as is, to report it freed if freed again.
+    unsafe fn remove(&mut self, block: usize, order: u32) {
+        let FreeBuddy { prev, next } = *(block as *mut FreeBuddy);
+
+        match prev.is_null() {
+            true => self.lists[order as usize] = next,
+            false => (*prev).next = next,
+        }
+        if !next.is_null() {
+            (*next).prev = prev;
+        }
+    }
+}
+
+/// order of the smallest block fitting `layout`, if any
+fn order_of(layout: Layout) -> Option<u32> {
+    let size = layout.size().max(layout.align()).max(MIN_BLOCK);
+    size.checked_next_power_of_two()
+        .map(usize::trailing_zeros)
+        .filter(|order| *order < ORDERS as u32 - 1)
+}
+
+#[cfg(test)]
+impl BuddyPalloc {
+    /// free blocks of every order, asserting that every free
+    /// block is recorded as such in the order table
+    pub(crate) fn free_blocks(&self) -> [usize; ORDERS] {
+        let mut counts = [0; ORDERS];
+        for (order, count) in counts.iter_mut().enumerate() {
+            let mut block = self.lists[order];
+            while !block.is_null() {
+                assert_eq!(unsafe { *self.entry(block as usize) }, order as u8);
+                assert!((block as usize).is_multiple_of(1 << order));
+
+                block = unsafe { (*block).next };
+                *count += 1;
+            }
+        }
+
+        counts
+    }
+}
+
+impl Default for BuddyPalloc {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+unsafe impl Send for BuddyPalloc {}
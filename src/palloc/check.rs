@@ -0,0 +1,314 @@
+use super::{
+    block::{BlockRef, MemoryBlock, ALIGN, MIN_CAPACITY},
+    free_list::FreeList,
+    Palloc, PallocError, Placement,
+};
+use core::{fmt, mem::size_of, ptr::null_mut};
+
+/// Corruption found by [`Palloc.check`](crate::Palloc::check)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HeapError {
+    /// header address of the first bad block
+    pub at: usize,
+    /// what is wrong with it
+    pub kind: HeapErrorKind,
+}
+
+/// The ways a block can be corrupted, see [`HeapError`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HeapErrorKind {
+    /// the block links to a misaligned address,
+    /// or to one outside of the heap
+    InvalidLink {
+        /// address of the next header
+        next: usize,
+    },
+    /// the block links to an address lower than its own
+    LinkNotIncreasing {
+        /// address of the next header
+        next: usize,
+    },
+    /// the allocation does not fit in the block
+    SizeOverflow {
+        /// size of the allocation
+        size: usize,
+        /// bytes up to the next header
+        capacity: usize,
+    },
+    /// the free block is too small to hold its free list links
+    /// and boundary tag
+    TooSmall {
+        /// bytes up to the next header
+        capacity: usize,
+    },
+    /// the boundary tag of the free block, or the flag
+    /// of the following one, is missing or wrong
+    BadTag,
+    /// the free block is followed by another free block,
+    /// they should have been merged
+    Unmerged,
+    /// the free list does not hold exactly the free blocks of the chain
+    FreeList,
+    /// the tail is not the last block of the chain, or it is allocated
+    MisplacedTail,
+}
+
+impl fmt::Display for HeapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "heap corrupted at {:#x}: ", self.at)?;
+        match self.kind {
+            HeapErrorKind::InvalidLink { next } => write!(f, "invalid link to {:#x}", next),
+            HeapErrorKind::LinkNotIncreasing { next } => {
+                write!(f, "link to {:#x} goes backwards", next)
+            }
+            HeapErrorKind::SizeOverflow { size, capacity } => write!(
+                f,
+                "allocation of {} bytes in a block of {} bytes",
+                size, capacity
+            ),
+            HeapErrorKind::TooSmall { capacity } => {
+                write!(f, "free block of {} bytes is too small", capacity)
+            }
+            HeapErrorKind::BadTag => f.write_str("wrong boundary tag"),
+            HeapErrorKind::Unmerged => f.write_str("free block followed by another one"),
+            HeapErrorKind::FreeList => f.write_str("free list out of sync with the chain"),
+            HeapErrorKind::MisplacedTail => f.write_str("misplaced tail"),
+        }
+    }
+}
+
+impl From<HeapError> for PallocError {
+    fn from(error: HeapError) -> Self {
+        PallocError::HeapCorrupted { at: error.at }
+    }
+}
+
+impl<P: Placement> Palloc<P> {
+    /// Walks the whole heap to verify its integrity, returning the first
+    /// corrupted block found, if any. Meant to run periodically in debug
+    /// builds, as it takes time linear in the number of blocks.
+    ///
+    /// Every link of the chain must be aligned, and strictly increasing
+    /// within a region. Every allocation must fit in its block, every free
+    /// block must be tagged, listed, and followed by an allocated one, and
+    /// the chain must end with the tail within the bounds of the heap.
+    ///
+    /// Links are checked before being followed, but the bounds of a region
+    /// are only known for the last one added: within the other ones, a
+    /// corrupted link may still lead to inaccessible memory between regions.
+    pub fn check(&self) -> Result<(), HeapError> {
+        if self.bottom.is_null() {
+            return Ok(());
+        }
+
+        let header = size_of::<MemoryBlock>();
+        let mut fences = self.regions - 1;
+        let mut listed = 0;
+        let mut block = unsafe { &*self.bottom };
+
+        loop {
+            let at = block.address();
+            let error = |kind| Err(HeapError { at, kind });
+
+            // the link is checked before any reference is made from it
+            let next_address = block.next_addr();
+            if next_address == 0 {
+                let tail = unsafe { self.free.tail() }.address();
+                if at != tail || block.is_allocated() || fences > 0 {
+                    return error(HeapErrorKind::MisplacedTail);
+                }
+                if block.heap() as usize > self.top {
+                    return error(HeapErrorKind::InvalidLink { next: at });
+                }
+
+                break;
+            }
+
+            let last_region = fences == 0;
+            if !self.in_heap(next_address) || (last_region && next_address + header > self.top) {
+                return error(HeapErrorKind::InvalidLink { next: next_address });
+            }
+
+            if block.is_fence() {
+                if last_region {
+                    return error(HeapErrorKind::InvalidLink { next: next_address });
+                }
+
+                fences -= 1;
+                block = unsafe { &*(next_address as *const MemoryBlock) };
+                continue;
+            }
+
+            if next_address < block.heap() as usize {
+                return error(HeapErrorKind::LinkNotIncreasing { next: next_address });
+            }
+            let next = unsafe { &*(next_address as *const MemoryBlock) };
+
+            let capacity = next_address - block.heap() as usize;
+            let free = !block.is_allocated();
+            if !free && block.size() > capacity {
+                return error(HeapErrorKind::SizeOverflow {
+                    size: block.size(),
+                    capacity,
+                });
+            }
+
+            if free {
+                if capacity < MIN_CAPACITY {
+                    return error(HeapErrorKind::TooSmall { capacity });
+                }
+
+                // the tag is read as is, it may not point to a block
+                let tag = unsafe { *((next_address - size_of::<usize>()) as *const usize) };
+                if !next.is_prev_free() || tag != at {
+                    return error(HeapErrorKind::BadTag);
+                }
+                if !next.is_allocated() {
+                    return error(HeapErrorKind::Unmerged);
+                }
+
+                listed += 1;
+            } else if next.is_prev_free() {
+                return error(HeapErrorKind::BadTag);
+            }
+
+            block = next;
+        }
+
+        self.check_free_list(listed)
+    }
+
+    /// verifies that the free list holds `listed` blocks, all free
+    /// and sorted, and that the rover is one of them
+    fn check_free_list(&self, listed: usize) -> Result<(), HeapError> {
+        let (mut prev, mut current) = (null_mut(), self.free.head());
+        let mut rover = self.free.rover().is_null();
+
+        for _ in 0..listed {
+            let error = Err(HeapError {
+                at: current as usize,
+                kind: HeapErrorKind::FreeList,
+            });
+            if current.is_null() || !(current as usize).is_multiple_of(ALIGN) || current <= prev {
+                return error;
+            }
+
+            let block = unsafe { &*current };
+            let links = unsafe { FreeList::links(current) };
+            if block.is_allocated() || !block.is_linked() || links.prev != prev {
+                return error;
+            }
+
+            rover |= current == self.free.rover();
+            (prev, current) = (current, links.next);
+        }
+
+        let at = match (current.is_null(), rover) {
+            (true, true) => return Ok(()),
+            (false, _) => current as usize,
+            (true, false) => self.free.rover() as usize,
+        };
+
+        Err(HeapError {
+            at,
+            kind: HeapErrorKind::FreeList,
+        })
+    }
+}
+
+impl<P: Placement> Palloc<P> {
+    /// whether a block header at `address` is aligned
+    /// and lies within the span of the heap
+    pub(super) fn in_heap(&self, address: usize) -> bool {
+        let end = self.span.1.max(self.top);
+        address.is_multiple_of(ALIGN)
+            && address >= self.span.0
+            && address
+                .checked_add(size_of::<MemoryBlock>())
+                .is_some_and(|header_end| header_end <= end)
+    }
+
+    /// whether the link of `block` leads to a header within the heap,
+    /// far enough for the block to hold the free list links
+    fn valid_link(&self, block: &MemoryBlock) -> bool {
+        let next = block.next_addr();
+        next >= block.heap() as usize + MIN_CAPACITY && self.in_heap(next)
+    }
+
+    /// Free blocks from `start` up to the end of the list, each one checked
+    /// before being handed out. The list is sorted, so a link going backwards
+    /// means a loop: the walk ends on the first corrupted block, reported as
+    /// [`HeapCorrupted`](PallocError::HeapCorrupted).
+    pub(super) fn walk_free(
+        &self,
+        start: *mut MemoryBlock,
+    ) -> impl Iterator<Item = Result<BlockRef, PallocError>> + '_ {
+        let (mut current, mut last) = (start, None);
+
+        core::iter::from_fn(move || {
+            let address = current as usize;
+            if current.is_null() {
+                return None;
+            }
+
+            // a bad link is reported on the block holding it
+            let at = last.unwrap_or(address);
+            current = null_mut();
+            if !self.in_heap(address) || last.is_some_and(|last| address <= last) {
+                return Some(Err(PallocError::HeapCorrupted { at }));
+            }
+
+            let block = unsafe { &mut *(address as *mut MemoryBlock) };
+            if block.is_allocated() || !self.valid_link(block) {
+                return Some(Err(PallocError::HeapCorrupted { at: address }));
+            }
+
+            last = Some(address);
+            current = unsafe { FreeList::links(block).next };
+            Some(Ok(block))
+        })
+    }
+
+    /// Verifies the allocated `block` and the free neighbours it is about
+    /// to be merged with: their links, and the boundary tag leading to
+    /// the preceding one.
+    pub(super) fn check_neighbours(&self, block: &MemoryBlock) -> Result<(), PallocError> {
+        let corrupted = |at| Err(PallocError::HeapCorrupted { at });
+
+        // links are checked before any reference is made from them
+        if !self.valid_link(block) {
+            return corrupted(block.address());
+        }
+        let next = unsafe { &*(block.next_addr() as *const MemoryBlock) };
+        if block
+            .max_size()
+            .is_some_and(|capacity| block.size() > capacity)
+        {
+            return corrupted(block.address());
+        }
+
+        // free neighbours are always merged, the tail included
+        let tail = unsafe { self.free.tail() }.address();
+        if !next.is_allocated() && next.address() != tail {
+            let merged = self.valid_link(next)
+                && unsafe { &*(next.next_addr() as *const MemoryBlock) }.is_allocated();
+            if !merged {
+                return corrupted(next.address());
+            }
+        }
+
+        if block.is_prev_free() {
+            let tag = unsafe { *((block.address() - size_of::<usize>()) as *const usize) };
+            if !self.in_heap(tag) || tag >= block.address() {
+                return corrupted(block.address());
+            }
+
+            let previous = unsafe { &*(tag as *const MemoryBlock) };
+            if previous.is_allocated() || previous.next_addr() != block.address() {
+                return corrupted(tag);
+            }
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,161 @@
+use super::block::{BlockRef, MemoryBlock};
+use core::{cell::Cell, ptr::null_mut};
+
+/// Links between free blocks, stored at the start of their payload.
+#[derive(Clone, Copy)]
+#[repr(C)]
+pub struct FreeLinks {
+    pub prev: *mut MemoryBlock,
+    pub next: *mut MemoryBlock,
+}
+
+/// Doubly linked list of the free blocks of a heap, sorted by address.
+///
+/// Allocations only walk the free blocks this way, while the chain of
+/// every block is left for merging neighbours. The tail is kept out of
+/// the list, as its payload may very well be empty: it is tracked on
+/// its own instead.
+pub struct FreeList {
+    head: Cell<*mut MemoryBlock>,
+    tail: Cell<*mut MemoryBlock>,
+    /// free block roving placements start from, null for the head
+    rover: Cell<*mut MemoryBlock>,
+}
+
+impl FreeList {
+    pub const fn new() -> Self {
+        Self {
+            head: Cell::new(null_mut()),
+            tail: Cell::new(null_mut()),
+            rover: Cell::new(null_mut()),
+        }
+    }
+
+    /// empties the list, leaving `tail` as the only free block
+    pub fn reset(&self, tail: *mut MemoryBlock) {
+        self.head.set(null_mut());
+        self.tail.set(tail);
+        self.rover.set(null_mut());
+    }
+
+    /// # Safety
+    /// `block` must be free and linked, with room for the links in its payload
+    pub unsafe fn links(block: *mut MemoryBlock) -> &'static mut FreeLinks {
+        &mut *((*block).heap() as *mut FreeLinks)
+    }
+
+    /// Inserts a free block and writes its boundary tag. The list is
+    /// walked to keep it sorted, unless one of its neighbours is free.
+    ///
+    /// # Safety
+    /// `block` must be free, linked and not in the list yet.
+    pub unsafe fn insert(&self, block: &mut MemoryBlock) {
+        let (mut prev, mut next) = (null_mut(), self.head.get());
+        let following = block.next().unwrap();
+
+        if let Some(previous) = block.previous_free() {
+            prev = previous as *mut MemoryBlock;
+            next = Self::links(prev).next;
+        } else if !following.is_allocated() && following.is_linked() {
+            next = following as *const MemoryBlock as *mut MemoryBlock;
+            prev = Self::links(next).prev;
+        } else {
+            while !next.is_null() && next < block as *mut MemoryBlock {
+                prev = next;
+                next = Self::links(next).next;
+            }
+        }
+
+        block.tag();
+        let block = block as *mut MemoryBlock;
+        *Self::links(block) = FreeLinks { prev, next };
+        match prev.is_null() {
+            true => self.head.set(block),
+            false => Self::links(prev).next = block,
+        }
+        if !next.is_null() {
+            Self::links(next).prev = block;
+        }
+    }
+
+    /// Removes a block from the list, clearing its boundary tag and moving
+    /// the rover to the following block when it points to it.
+    ///
+    /// # Safety
+    /// `block` must be in the list.
+    pub unsafe fn remove(&self, block: &mut MemoryBlock) {
+        block.untag();
+        let block = block as *mut MemoryBlock;
+        let FreeLinks { prev, next } = *Self::links(block);
+
+        match prev.is_null() {
+            true => self.head.set(next),
+            false => Self::links(prev).next = next,
+        }
+        if !next.is_null() {
+            Self::links(next).prev = prev;
+        }
+        if self.rover.get() == block {
+            self.rover.set(next);
+        }
+    }
+
+    /// block following `block` in the list, if any
+    ///
+    /// # Safety
+    /// `block` must be in the list.
+    pub unsafe fn next(&self, block: &MemoryBlock) -> *mut MemoryBlock {
+        Self::links(block as *const MemoryBlock as *mut MemoryBlock).next
+    }
+
+    #[inline]
+    pub fn head(&self) -> *mut MemoryBlock {
+        self.head.get()
+    }
+
+    /// # Safety
+    /// The list must have been reset with a valid tail.
+    pub unsafe fn tail(&self) -> BlockRef {
+        &mut *self.tail.get()
+    }
+
+    #[inline]
+    pub fn set_tail(&self, tail: &mut MemoryBlock) {
+        self.tail.set(tail);
+    }
+
+    #[inline]
+    pub fn rover(&self) -> *mut MemoryBlock {
+        self.rover.get()
+    }
+
+    #[inline]
+    pub fn set_rover(&self, rover: *mut MemoryBlock) {
+        self.rover.set(rover);
+    }
+}
+
+#[cfg(test)]
+pub struct FreeIterator {
+    current: *mut MemoryBlock,
+}
+
+#[cfg(test)]
+impl FreeIterator {
+    /// iterates over the blocks linked from `start`
+    pub fn new(start: *mut MemoryBlock) -> Self {
+        Self { current: start }
+    }
+}
+
+#[cfg(test)]
+impl Iterator for FreeIterator {
+    type Item = BlockRef;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = unsafe { self.current.as_mut() }?;
+        self.current = unsafe { FreeList::links(current).next };
+
+        Some(current)
+    }
+}
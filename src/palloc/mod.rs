@@ -1,7 +1,36 @@
 mod block;
+mod blocks;
+mod buddy;
+mod check;
+mod free_list;
+mod morecore;
+mod placement;
+mod segregated;
+mod stats;
+mod tlsf;
 
-use block::{BlockRef, MemoryBlock};
-use core::ptr::{null_mut, NonNull};
+pub use blocks::{BlockInfo, Blocks};
+pub use buddy::BuddyPalloc;
+pub use check::{HeapError, HeapErrorKind};
+pub use morecore::MoreCore;
+pub use placement::{BestFit, FirstFit, NextFit, Placement, WorstFit};
+pub use segregated::SegregatedPalloc;
+pub use stats::{Fragmentation, PallocStats};
+pub use tlsf::TlsfPalloc;
+
+use free_list::FreeList;
+use stats::Usage;
+
+use block::{
+    align_up, capacity_for, BlockIterator, BlockRef, MemoryBlock, ALIGN, MAX_SIZE, MIN_CAPACITY,
+};
+use core::{
+    alloc::Layout,
+    fmt,
+    marker::PhantomData,
+    mem::size_of,
+    ptr::{null_mut, NonNull},
+};
 
 /// defines an error returned from either an allocation
 /// or a deallocation
@@ -24,6 +53,47 @@ pub enum PallocError {
     OutOfMemory,
     /// given is zero or memory header controlling it is zero
     NullPtr,
+    /// the pointer lies outside of the heap region, it
+    /// was never handed out by this allocator.
+    ForeignPointer,
+    /// the pointer lies within the heap region but it does
+    /// not point to the start of an allocation.
+    InvalidPointer,
+    /// the allocation at `addr` has already been freed,
+    /// `size` is the size it was allocated with.
+    DoubleFree {
+        /// address passed for deallocation
+        addr: usize,
+        /// size of the allocation before it was freed
+        size: usize,
+    },
+    /// a block header or a free list link has been overwritten,
+    /// see [`Palloc.check`](crate::Palloc::check)
+    HeapCorrupted {
+        /// header address of the corrupted block
+        at: usize,
+    },
+}
+
+impl fmt::Display for PallocError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoBlockSpace => f.write_str("not enough space in block"),
+            Self::AlreadyAllocated => f.write_str("block already allocated"),
+            Self::NotAllocated => f.write_str("block not allocated"),
+            Self::SegmentingTail => f.write_str("cannot segment the tail block"),
+            Self::OutOfMemory => f.write_str("out of memory"),
+            Self::NullPtr => f.write_str("null pointer"),
+            Self::ForeignPointer => f.write_str("pointer outside of the heap region"),
+            Self::InvalidPointer => f.write_str("pointer is not the start of an allocation"),
+            Self::DoubleFree { addr, size } => write!(
+                f,
+                "double free of {:#x} (previously allocated with {} bytes)",
+                addr, size
+            ),
+            Self::HeapCorrupted { at } => write!(f, "heap corrupted at {:#x}", at),
+        }
+    }
 }
 
 /// defines a both uninitialized and initialized allocator.
@@ -31,13 +101,34 @@ pub enum PallocError {
 /// An ['empty'](#method.empty) instance may be created for static purposes,
 /// but in order to allocate memory [initialization](#method.init) must occur.
 ///
+/// The free block each allocation is placed in is chosen by the
+/// [`Placement`] strategy `P`, [`FirstFit`] unless specified otherwise.
+/// Free blocks are linked together through their payload, so that the
+/// search never walks over allocated ones, and end with a boundary tag
+/// for finding their preceding neighbour in constant time. This is why every
+/// block takes at least three words of payload. The free list is kept sorted
+/// by address, so freeing a block with no free neighbour walks it.
+///
+/// The heap may span several regions, see [`add_region`](#method.add_region).
+///
 /// # Safety
 /// Palloc manually implements the Send trait, meaning it can be sended between threads
 /// for shared access. This also means that the heap memory region must be
 /// accessible from every thread.
-pub struct Palloc {
+pub struct Palloc<P = FirstFit> {
     bottom: *mut MemoryBlock,
-    size: usize,
+    /// end of the region holding the tail, the last one added
+    top: usize,
+    /// number of regions of the heap, see [`add_region`](#method.add_region)
+    regions: usize,
+    /// lowest address of the heap and end of the highest region
+    /// but the last one, which every link must lie within
+    span: (usize, usize),
+    free: FreeList,
+    /// called to grow the heap once out of memory
+    morecore: Option<&'static mut dyn MoreCore>,
+    usage: Usage,
+    placement: PhantomData<P>,
 }
 
 impl Palloc {
@@ -45,31 +136,205 @@ impl Palloc {
     ///
     /// to make the allocator working, check out [`init`](#method.init)
     pub const fn empty() -> Palloc {
+        Palloc::new()
+    }
+}
+
+impl<P: Placement> Palloc<P> {
+    /// creates an empty allocator using the `P` placement strategy.
+    /// See [`empty`](#method.empty)
+    pub const fn new() -> Palloc<P> {
         Palloc {
             bottom: null_mut(),
-            size: 0,
+            top: 0,
+            regions: 0,
+            span: (0, 0),
+            free: FreeList::new(),
+            morecore: None,
+            usage: Usage::new(),
+            placement: PhantomData,
         }
     }
 
-    unsafe fn get_origin(&self) -> BlockRef {
-        NonNull::new_unchecked(self.bottom).as_mut()
-    }
-
     /// Initializes the allocator with a pointer to a free heap region
     /// and a size which defines the upper bound of the same.
     ///
+    /// The bottom is rounded up to the alignment of a block header,
+    /// the few bytes skipped this way are not used by the allocator.
+    ///
     /// ### Safety
     /// Memory is not asserted to be zeroed. However the whole region must
     /// be accessible and free to use.
     ///
-    /// Initializing using a null pointer will result in a panic.
+    /// Initializing using a null pointer or a region too small to hold
+    /// a single block header will result in a panic.
     pub unsafe fn init(&mut self, bottom: NonNull<u8>, size: usize) {
-        let bottom = bottom.cast();
+        let (bottom, top) = region_bounds(bottom, size);
 
         self.bottom = bottom.as_ptr();
-        self.size = size;
+        self.top = top;
+        self.regions = 1;
+        self.span = (bottom.as_ptr() as usize, 0);
 
         MemoryBlock::default_from_ptr(bottom);
+        self.free.reset(bottom.as_ptr());
+        self.usage = Usage::new();
+    }
+
+    /// Adds a region of `size` bytes to the heap, which may lie anywhere
+    /// in memory, before or after the ones already managed. Adding a region
+    /// to an empty allocator initializes it, see [`init`](#method.init).
+    ///
+    /// Blocks are never merged across regions: the unused end of the
+    /// previous region becomes a free block of its own, followed by a
+    /// header allocated for good, the fence, linking it to the new region.
+    /// The new region holds the tail from now on.
+    ///
+    /// ### Safety
+    /// See [`init`](#method.init). The region must not overlap
+    /// with any other region of the heap.
+    pub unsafe fn add_region(&mut self, bottom: NonNull<u8>, size: usize) {
+        if self.bottom.is_null() {
+            return self.init(bottom, size);
+        }
+
+        let (bottom, top) = region_bounds(bottom, size);
+        let first = MemoryBlock::default_from_ptr(bottom) as *mut MemoryBlock;
+
+        // the tail is free, it may be preceded by allocated blocks only
+        let tail = self.free.tail();
+        let fence = (self.top - size_of::<MemoryBlock>()) & !(ALIGN - 1);
+
+        if fence >= tail.heap() as usize + MIN_CAPACITY {
+            tail.insert_default(NonNull::new_unchecked(fence as *mut _));
+            tail.next_mut().unwrap().fence(&mut *first);
+            self.free.insert(tail);
+        } else {
+            // too small to be a free block, the tail is the fence itself
+            tail.fence(&mut *first);
+        }
+
+        self.free.set_tail(&mut *first);
+        self.span = (self.span.0.min(first as usize), self.span.1.max(self.top));
+        self.top = top;
+        self.regions += 1;
+    }
+
+    /// Grows the heap by `additional` bytes, right after its current end,
+    /// that is the end of the last region added. The tail grows along.
+    ///
+    /// ### Safety
+    /// The allocator must be initialized, and the `additional` bytes
+    /// following the heap must be accessible and free to use.
+    pub unsafe fn extend(&mut self, additional: usize) {
+        assert!(!self.bottom.is_null(), "cannot extend an empty heap");
+        self.top = self.top.checked_add(additional).expect("heap overflows");
+    }
+
+    /// Gives back every free byte at the end of the heap, so that it ends
+    /// right after the tail header. Returns the number of bytes released,
+    /// which are not used by the allocator anymore unless given back
+    /// through [`extend`](#method.extend).
+    ///
+    /// Freed blocks are merged right away, so the tail always starts right
+    /// after the highest allocated block of the last region added.
+    pub fn trim(&mut self) -> usize {
+        if self.bottom.is_null() {
+            return 0;
+        }
+
+        let tail = unsafe { self.free.tail() };
+        self.release_to(tail.heap() as usize)
+    }
+
+    /// Shrinks the last region added to `size` bytes past its first block,
+    /// or as close to it as the blocks allocated there allow. Returns the
+    /// number of bytes released, see [`trim`](#method.trim).
+    pub fn shrink_to(&mut self, size: usize) -> usize {
+        let Some((first, _)) = (unsafe { self.regions() }).last() else {
+            return 0;
+        };
+
+        let tail = unsafe { self.free.tail() };
+        let top = first.address().saturating_add(size);
+        self.release_to(top.max(tail.heap() as usize))
+    }
+
+    /// moves the end of the heap down to `top`, if lower
+    fn release_to(&mut self, top: usize) -> usize {
+        let released = self.top.saturating_sub(top);
+        self.top -= released;
+        released
+    }
+
+    /// Iterates over every block of the heap, from the bottom of the first
+    /// region to the tail, describing each one with a [`BlockInfo`]. Blocks
+    /// cached by an allocator built on top, like [`SegregatedPalloc`], are
+    /// reported as free, while the fences linking regions are skipped.
+    ///
+    /// The iterator borrows the allocator, so that nothing is allocated while
+    /// it lives. Freeing only takes `&self` though, and must wait for the
+    /// iteration to be over.
+    pub fn blocks(&self) -> Blocks<'_> {
+        Blocks::new(self.bottom, self.top)
+    }
+
+    /// Walks every block of the heap to report its usage, see [`PallocStats`].
+    /// Blocks cached by an allocator built on top, like [`SegregatedPalloc`],
+    /// are reported as free.
+    pub fn stats(&self) -> PallocStats {
+        let mut stats = PallocStats {
+            peak: self.usage.peak(),
+            ..Default::default()
+        };
+
+        let Some(origin) = NonNull::new(self.bottom) else {
+            return stats;
+        };
+
+        let header = size_of::<MemoryBlock>();
+        for block in BlockIterator::new(origin.as_ptr()) {
+            if block.is_fence() {
+                stats.overhead += header;
+                continue;
+            }
+
+            let capacity = block
+                .max_size()
+                .unwrap_or_else(|| self.top - block.heap() as usize);
+
+            if block.is_allocated() && !block.is_cached() {
+                stats.used += block.size();
+                stats.overhead += header + capacity - block.size();
+                stats.allocated_blocks += 1;
+            } else {
+                stats.free += capacity;
+                stats.overhead += header;
+                stats.free_blocks += 1;
+                stats.largest_free = stats.largest_free.max(capacity);
+            }
+        }
+
+        stats
+    }
+
+    /// Walks the free blocks of the heap to report how fragmented its free
+    /// memory is, see [`Fragmentation`]. Blocks cached by an allocator built
+    /// on top, like [`SegregatedPalloc`], are reported as free.
+    pub fn fragmentation(&self) -> Fragmentation {
+        let free = self.blocks().filter(|block| !block.allocated);
+        free.fold(Fragmentation::new(), |mut fragmentation, block| {
+            fragmentation.add(block.capacity);
+            fragmentation
+        })
+    }
+
+    /// Sets the source of memory called when the heap runs out of it,
+    /// before reporting [`OutOfMemory`](PallocError::OutOfMemory). The
+    /// memory it provides is added to the heap like [`extend`](#method.extend)
+    /// would, and the allocation retried.
+    pub fn set_morecore(&mut self, morecore: &'static mut dyn MoreCore) {
+        self.morecore = Some(morecore);
     }
 
     /// Initializes heap from a memory slice. See [`init`](#method.init) for more informations.
@@ -88,47 +353,247 @@ impl Palloc {
     /// May result in one of the errors defined in
     /// [`PallocError`](enum.PallocError.html).
     ///
-    /// Alloc will potentially traverse the entire heap in search of a free segment.
-    /// It will also merge all freed adjacent blocks. Once a free block is found, if
+    /// Alloc will potentially traverse every free block in search of a free segment,
+    /// chosen by the [`Placement`] strategy. Once a free block is found, if
     /// it does not fill the entire segment (in case of reallocation) a chunk will be split
     /// and the rest of the memory made available for further allocations.
     ///
     /// This whole process, while not ensuring super fast allocation all of the time, it
     /// assures that every piece of memory is being used as much as possible.
     ///
+    /// The returned pointer is aligned to `usize`, use
+    /// [`alloc_layout`](#method.alloc_layout) when a stricter alignment is needed.
+    ///
+    /// Zero sized allocations are valid: each one still consumes a block header,
+    /// is given a distinct pointer and must be freed like any other allocation.
+    ///
+    /// Every free block is checked before being considered: a link leading out
+    /// of the heap or back to a block already walked over stops the search
+    /// with [`HeapCorrupted`](PallocError::HeapCorrupted), rather than following
+    /// it wherever it leads.
+    ///
     /// ### Safety
     /// Null pointer is never returned, in case of OOM a PallocError is returned
     /// instead. As stated before, memory is never to be assumed initialized.
     pub unsafe fn alloc(&mut self, size: usize) -> Result<NonNull<u8>, PallocError> {
-        let origin = self.get_origin(); // base memory block starting from bottom
-        let list = origin.iter_mut();
-
-        for block in list.filter(|block| !block.is_allocated()) {
-            match block.max_size() {
-                Some(max) if max < size => {
-                    if block.merge(size).is_err() {
-                        continue;
-                    }
+        let layout = Layout::from_size_align(size, 1).or(Err(PallocError::OutOfMemory))?;
+        self.alloc_layout(layout)
+    }
+
+    /// Creates a new allocation fitting `layout`, returning a pointer aligned
+    /// to `layout.align()`. Behaves like [`alloc`](#method.alloc) otherwise.
+    ///
+    /// When the start of a free block is not suitably aligned, the bytes
+    /// preceding the aligned address are split off into a free block of their
+    /// own, so that they stay available for further allocations.
+    ///
+    /// ### Safety
+    /// See [`alloc`](#method.alloc)
+    pub unsafe fn alloc_layout(&mut self, layout: Layout) -> Result<NonNull<u8>, PallocError> {
+        let start = match self.free.rover() {
+            rover if P::ROVING && !rover.is_null() => rover,
+            _ => self.free.head(),
+        };
+
+        // from the start of the search up to the last free block, then wrapping around
+        let start_address = start as usize;
+        let list = self
+            .walk_free(start)
+            .chain(self.walk_free(self.free.head()).take_while(move |block| {
+                block
+                    .as_ref()
+                    .map_or(true, |block| block.address() < start_address)
+            }));
+
+        // padding plus the allocation itself, which must leave room
+        // for the free list links and the tag once freed
+        let fitting_size = |block: &MemoryBlock| {
+            block
+                .padding(layout.align())
+                .checked_add(capacity_for(layout.size()))
+                .filter(|size| *size <= MAX_SIZE)
+                .ok_or(PallocError::OutOfMemory)
+        };
+
+        let mut chosen: Option<(BlockRef, usize)> = None;
+
+        for block in list {
+            let block = block?;
+            let size = fitting_size(block)?;
+            // blocks in the free list are never the tail
+            let max = block.max_size().unwrap();
+            if max >= size {
+                let rank = P::rank(max, size);
+                if chosen.as_ref().is_none_or(|(_, best)| rank < *best) {
+                    chosen = Some((block, rank));
+                }
+
+                if rank == 0 {
+                    break;
                 }
-                _ => (),
             }
+        }
+
+        let block = match chosen {
+            Some((block, _)) => block,
+            None => {
+                let tail = self.free.tail();
+                if tail.is_allocated() || tail.next_addr() != 0 || !self.in_heap(tail.address()) {
+                    return Err(PallocError::HeapCorrupted { at: tail.address() });
+                }
 
-            let is_tail = !block.is_linked();
-            if is_tail && block.heap() as usize + size > self.bottom as usize + self.size {
-                return Err(PallocError::OutOfMemory);
+                let size = fitting_size(tail)?;
+                match self.tail_fits(tail, size) || self.grow(tail, size) {
+                    true => tail,
+                    false => return Err(PallocError::OutOfMemory),
+                }
             }
+        };
 
-            let allocation = block.allocate(size)?;
-            if is_tail {
-                block.link_default();
-            } else {
-                block.segment()?;
+        let is_tail = !block.is_linked();
+        let padding = block.padding(layout.align());
+
+        let block = block.split_padding(padding, &self.free);
+        let next_free = match is_tail {
+            true => null_mut(),
+            false => self.free.next(block),
+        };
+
+        let allocation = block.allocate(layout.size(), &self.free)?;
+        if is_tail {
+            block.link_default(&self.free);
+        } else {
+            block.segment(&self.free)?;
+        }
+
+        self.advance_rover(block, next_free);
+        self.usage.add(layout.size());
+        Ok(NonNull::new_unchecked(allocation))
+    }
+
+    /// Moves the roving pointer to the first free block following the
+    /// allocation on `block`: either the memory split off from it or
+    /// `next_free`, the block that followed it in the free list.
+    fn advance_rover(&self, block: &MemoryBlock, next_free: *mut MemoryBlock) {
+        let rover = match block.next() {
+            // the tail is never roved over, the search starts from the bottom instead
+            Some(split) if !split.is_allocated() && !split.is_linked() => null_mut(),
+            Some(split) if !split.is_allocated() => split as *const MemoryBlock as *mut _,
+            _ => next_free,
+        };
+
+        self.free.set_rover(rover);
+    }
+
+    /// Resizes the allocation at `alloc` to `new_size` bytes, preserving its
+    /// content up to the smaller of the two sizes. Returns the pointer to the
+    /// resized allocation, which is `alloc` itself whenever possible.
+    ///
+    /// Growing absorbs the free blocks that follow the allocation, tail included,
+    /// while shrinking splits the unused end off into a free block. Only when the
+    /// allocation cannot grow in place it is moved to a new one, and `alloc` freed.
+    ///
+    /// ### Safety
+    /// `alloc` must point to the bottom of a valid allocation, see [`free`](#method.free).
+    /// On error the original allocation is left untouched.
+    pub unsafe fn realloc(
+        &mut self,
+        alloc: NonNull<u8>,
+        new_size: usize,
+    ) -> Result<NonNull<u8>, PallocError> {
+        let layout = Layout::from_size_align(new_size, 1).or(Err(PallocError::OutOfMemory))?;
+        self.realloc_layout(alloc, layout)
+    }
+
+    /// Resizes the allocation at `alloc` to fit `layout`, returning a pointer aligned
+    /// to `layout.align()`. Behaves like [`realloc`](#method.realloc) otherwise.
+    ///
+    /// ### Safety
+    /// See [`realloc`](#method.realloc)
+    pub unsafe fn realloc_layout(
+        &mut self,
+        alloc: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, PallocError> {
+        let block = MemoryBlock::from_heap_ptr(alloc).ok_or(PallocError::NullPtr)?;
+        if !block.is_allocated() {
+            return Err(PallocError::NotAllocated);
+        }
+
+        self.check_neighbours(block)?;
+
+        let old_size = block.size();
+        let aligned = (alloc.as_ptr() as usize).is_multiple_of(layout.align());
+        if aligned && self.resize_in_place(block, layout.size()) {
+            self.usage.sub(old_size);
+            self.usage.add(layout.size());
+            return Ok(alloc);
+        }
+
+        let moved = self.alloc_layout(layout)?;
+        core::ptr::copy_nonoverlapping(alloc.as_ptr(), moved.as_ptr(), old_size.min(layout.size()));
+        self.free(alloc)?;
+
+        Ok(moved)
+    }
+
+    /// tries to fit `size` bytes in the allocated `block` without moving it,
+    /// returning whatever is left unused to the heap.
+    unsafe fn resize_in_place(&self, block: &mut MemoryBlock, size: usize) -> bool {
+        let merged = block.merge(size, &self.free).is_ok();
+
+        if !block.is_linked() {
+            // the tail has been absorbed, it must be placed back
+            // right after the allocation, whatever its size ends up being
+            let fits = self.tail_fits(block, size);
+            if fits {
+                block.resize(size);
             }
 
-            return Ok(NonNull::new_unchecked(allocation));
+            block.link_default(&self.free);
+            return fits;
+        }
+
+        if merged {
+            block.resize(size);
         }
 
-        panic!("a valid candidate must be found before the loop ends")
+        // block is allocated and linked, segmenting cannot fail
+        let _ = block.segment(&self.free);
+
+        merged
+    }
+
+    /// whether `size` bytes can be allocated on the tail `block`. The
+    /// allocation is followed by a new tail header, which must fit
+    /// within the heap bounds as well.
+    fn tail_fits(&self, block: &MemoryBlock, size: usize) -> bool {
+        let end = capacity_for(size) + size_of::<MemoryBlock>();
+        (block.heap() as usize).saturating_add(end) <= self.top
+    }
+
+    /// asks the morecore, if any, for the memory missing to
+    /// allocate `size` bytes on the tail `block`
+    fn grow(&mut self, block: &MemoryBlock, size: usize) -> bool {
+        let Some(morecore) = self.morecore.as_deref_mut() else {
+            return false;
+        };
+
+        let end = (capacity_for(size) + size_of::<MemoryBlock>())
+            .checked_add(block.heap() as usize)
+            .filter(|end| *end > self.top);
+        let Some(missing) = end.map(|end| end - self.top) else {
+            return false;
+        };
+
+        let top = unsafe { NonNull::new_unchecked(self.top as *mut u8) };
+        match morecore.more_core(top, missing) {
+            Some(additional) if additional >= missing => {
+                self.top += additional;
+                true
+            }
+            _ => false,
+        }
     }
 
     /// Deallocates memory at a given pointer location, giving it back to
@@ -137,17 +602,255 @@ impl Palloc {
     /// Once deallocated, memory cannot be used anymore and
     /// its integrity is not assured.
     ///
+    /// The freed block is merged right away with the free blocks surrounding it,
+    /// so that free memory does not stay fragmented until the next allocation
+    /// walks over it. Free blocks end with a boundary tag pointing to their header,
+    /// so the preceding block is found in constant time, and merging only takes
+    /// constant time as well. A block with no free neighbour is inserted in the
+    /// free list instead, which is sorted by address: this walks the free blocks
+    /// preceding it, in time linear in their number.
+    ///
+    /// Freed blocks keep the size they were allocated with, even once merged
+    /// into a neighbour, so that freeing them again is reported as
+    /// [`DoubleFree`](PallocError::DoubleFree) for as long as their memory is
+    /// not handed out again.
+    ///
+    /// The neighbours of the block are checked before being merged with it,
+    /// a corrupted link or boundary tag being reported as
+    /// [`HeapCorrupted`](PallocError::HeapCorrupted), leaving the block allocated.
+    ///
     /// ### Safety
     /// `alloc` must point to the bottom of a valid allocation. Not being aligned to
     /// one will lead to **undefined behaviour**, potentially destructive.
     pub unsafe fn free(&self, alloc: NonNull<u8>) -> Result<(), PallocError> {
         let block = MemoryBlock::from_heap_ptr(alloc).ok_or(PallocError::NullPtr)?;
-        if block.is_allocated() {
-            block.dealloc()
-        } else {
-            Err(PallocError::NotAllocated)
+        if !block.is_allocated() {
+            return Err(PallocError::DoubleFree {
+                addr: alloc.as_ptr() as usize,
+                size: block.size(),
+            });
+        }
+
+        self.check_neighbours(block)?;
+
+        // cached blocks are not accounted as used anymore
+        if !block.is_cached() {
+            self.usage.sub(block.size());
+        }
+
+        block.dealloc()?;
+        self.free.insert(block);
+        block.coalesce(&self.free);
+
+        if let Some(previous) = block.previous_free() {
+            previous.coalesce(&self.free);
         }
+
+        Ok(())
+    }
+
+    /// Deallocates memory like [`free`](#method.free), but validates `alloc`
+    /// first instead of trusting it.
+    ///
+    /// The pointer is range-checked against the heap region and the block chain
+    /// is walked to make sure it points to the start of an allocation, returning
+    /// [`ForeignPointer`](PallocError::ForeignPointer) or
+    /// [`InvalidPointer`](PallocError::InvalidPointer) otherwise. This makes
+    /// deallocation linear in the number of blocks.
+    ///
+    /// Unlike [`free`](#method.free), a pointer to a block that has been merged
+    /// into a free neighbour is reported as [`DoubleFree`](PallocError::DoubleFree)
+    /// only when it lies within free memory.
+    ///
+    /// ### Safety
+    /// Any pointer is accepted. Links of the block chain going backwards or
+    /// out of the region are reported as [`HeapCorrupted`](PallocError::HeapCorrupted),
+    /// but a corrupted link may still lead to inaccessible memory within it.
+    pub unsafe fn free_checked(&self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        self.check_pointer(alloc)?;
+        self.free(alloc)
+    }
+
+    /// succeeds when `alloc` points to the start of a block,
+    /// see [`free_checked`](#method.free_checked)
+    pub(crate) unsafe fn check_pointer(&self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        let address = alloc.as_ptr() as usize;
+        let (first, end) = self
+            .regions()
+            .find(|(first, end)| (first.address()..*end).contains(&address))
+            .ok_or(PallocError::ForeignPointer)?;
+
+        check_pointer(first, end - first.address(), alloc)
+    }
+
+    /// First block and end of every region, in the order they have been
+    /// added. Regions but the last one end with the header of their fence,
+    /// searched for as long as the links lead forward within the heap.
+    unsafe fn regions(&self) -> impl Iterator<Item = (BlockRef, usize)> + '_ {
+        let mut first = NonNull::new(self.bottom);
+        let mut fences = self.regions.saturating_sub(1);
+        let top = self.top;
+
+        core::iter::from_fn(move || {
+            let start = first.take()?.as_ptr();
+            if fences == 0 {
+                return Some((&mut *start, top));
+            }
+
+            // links are read as raw words, and followed once checked
+            fences -= 1;
+            let (mut block, mut end) = (start, top);
+            loop {
+                let next = (*block).next_addr();
+                if (*block).is_fence() {
+                    end = (*block).heap() as usize;
+                    first = NonNull::new(next as *mut MemoryBlock).filter(|_| self.in_heap(next));
+                    break;
+                }
+                if next <= block as usize || !self.in_heap(next) {
+                    break;
+                }
+
+                block = next as *mut MemoryBlock;
+            }
+
+            Some((&mut *start, end))
+        })
+    }
+
+    /// deallocation used by the global allocators, checked
+    /// only when the `checked_free` feature is enabled.
+    pub(crate) unsafe fn global_free(&self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        self.global_check(alloc)?;
+        self.free(alloc)
+    }
+
+    /// pointer validation of the global allocators, performed
+    /// only when the `checked_free` feature is enabled.
+    pub(crate) unsafe fn global_check(&self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        match cfg!(feature = "checked_free") {
+            true => self.check_pointer(alloc),
+            false => Ok(()),
+        }
+    }
+}
+
+/// Rounds the bottom of a region up to the alignment of a block header,
+/// returning it along with the end of the region.
+///
+/// # Panics
+/// Panics when the region cannot hold a single block header.
+fn region_bounds(bottom: NonNull<u8>, size: usize) -> (NonNull<MemoryBlock>, usize) {
+    let start = bottom.as_ptr() as usize;
+    let aligned = align_up(start, ALIGN);
+    assert!(
+        size.saturating_sub(aligned - start) >= size_of::<MemoryBlock>(),
+        "heap region must fit at least a block header"
+    );
+
+    let bottom = unsafe { NonNull::new_unchecked(aligned as *mut MemoryBlock) };
+    (bottom, start + size)
+}
+
+/// Succeeds when `alloc` points to the start of a block of the chain
+/// starting at `origin`, spanning `size` bytes. Stale headers left
+/// within free memory by merges are reported as double frees.
+///
+/// # Safety
+/// Links are checked to go forward within the region before being
+/// followed, which must be accessible as a whole.
+unsafe fn check_pointer(
+    origin: BlockRef,
+    size: usize,
+    alloc: NonNull<u8>,
+) -> Result<(), PallocError> {
+    let address = alloc.as_ptr() as usize;
+    let (bottom, top) = (origin.address(), origin.address() + size);
+    if address < bottom + size_of::<MemoryBlock>() || address >= top {
+        return Err(PallocError::ForeignPointer);
+    }
+
+    // last block starting at or before the pointer, the links
+    // leading there must go forward within the region
+    let mut block = origin;
+    loop {
+        let link = block.next_addr();
+        if link == 0 || link.saturating_add(size_of::<MemoryBlock>()) > address {
+            break;
+        }
+
+        if link <= block.address()
+            || link + size_of::<MemoryBlock>() > top
+            || !link.is_multiple_of(ALIGN)
+        {
+            return Err(PallocError::HeapCorrupted {
+                at: block.address(),
+            });
+        }
+
+        block = &mut *(link as *mut MemoryBlock);
+    }
+
+    if block.heap() as usize == address {
+        return Ok(());
+    }
+
+    // a stale header within free memory, left behind by a merge
+    let header = address - size_of::<MemoryBlock>();
+    if block.is_allocated() || header < block.heap() as usize || !header.is_multiple_of(ALIGN) {
+        return Err(PallocError::InvalidPointer);
+    }
+
+    match MemoryBlock::from_heap_ptr(alloc) {
+        Some(stale) if !stale.is_allocated() => Err(PallocError::DoubleFree {
+            addr: address,
+            size: stale.size(),
+        }),
+        _ => Err(PallocError::InvalidPointer),
+    }
+}
+
+#[cfg(test)]
+impl<P: Placement> Palloc<P> {
+    unsafe fn get_origin(&self) -> BlockRef {
+        NonNull::new_unchecked(self.bottom).as_mut()
+    }
+
+    pub(crate) fn free_blocks(&self) -> usize {
+        unsafe { self.get_origin() }
+            .iter_mut()
+            .filter(|block| !block.is_allocated())
+            .count()
+    }
+
+    /// asserts that every block following a free one is flagged
+    /// and that its boundary tag points back to it
+    pub(crate) fn assert_tags(&self) {
+        for block in unsafe { self.get_origin() }.iter_mut() {
+            let Some(next) = block.next() else { break };
+            let free = !block.is_allocated();
+            assert_eq!(next.is_prev_free(), free);
+            if free {
+                let tagged = unsafe { next.previous_free() }.unwrap();
+                assert_eq!(tagged.address(), block.address());
+            }
+        }
+    }
+
+    /// free blocks in the free list, which must be sorted by address
+    pub(crate) fn listed_blocks(&self) -> usize {
+        let list = self.walk_free(self.free.head());
+        list.fold(0, |count, block| {
+            assert!(block.is_ok(), "free list should be sorted");
+            count + 1
+        })
+    }
+}
+
+impl<P: Placement> Default for Palloc<P> {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
-unsafe impl Send for Palloc {}
+unsafe impl<P> Send for Palloc<P> {}
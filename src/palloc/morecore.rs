@@ -0,0 +1,17 @@
+use core::ptr::NonNull;
+
+/// Source of memory for a [`Palloc`](crate::Palloc) growing past the end
+/// of its heap, see [`Palloc.set_morecore`](crate::Palloc::set_morecore).
+///
+/// Called once an allocation fits neither in a free block nor on the tail,
+/// to map or unlock the memory following the heap, like `sbrk` would.
+///
+/// # Safety
+/// Bytes reported by [`more_core`](#tymethod.more_core) must be accessible
+/// and free to use, for as long as the allocator is.
+pub unsafe trait MoreCore: Send {
+    /// Makes at least `min` more bytes available right after `top`, the
+    /// current end of the heap. Returns the number of bytes made available,
+    /// `None` when the heap cannot grow that much.
+    fn more_core(&mut self, top: NonNull<u8>, min: usize) -> Option<usize>;
+}
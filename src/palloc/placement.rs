@@ -0,0 +1,69 @@
+/// Strategy used by [`Palloc`](crate::Palloc) to choose the free block an
+/// allocation is placed in.
+///
+/// Every free block large enough for the allocation is given a rank, and the
+/// one with the lowest rank is chosen. A rank of zero ends the search right
+/// away. The tail of the heap is used only when no free block fits.
+///
+/// Custom strategies can be implemented as well, only [`rank`](Placement::rank)
+/// must be provided.
+pub trait Placement {
+    /// Whether the search starts from the block of the previous allocation
+    /// instead of the bottom of the heap, wrapping around once the end of
+    /// the heap is reached.
+    const ROVING: bool = false;
+
+    /// Ranks a free block of `capacity` bytes for an allocation of `size` bytes.
+    /// `capacity` is always greater or equal than `size`.
+    fn rank(capacity: usize, size: usize) -> usize;
+}
+
+/// Places allocations in the first free block that fits, starting from
+/// the bottom of the heap. This is the default placement.
+pub struct FirstFit;
+
+impl Placement for FirstFit {
+    fn rank(_: usize, _: usize) -> usize {
+        0
+    }
+}
+
+/// Places allocations in the first free block that fits, starting from the
+/// block of the previous allocation.
+///
+/// The search does not walk over the same crowded blocks at the bottom of the
+/// heap over and over, at the cost of spreading allocations over the whole heap.
+pub struct NextFit;
+
+impl Placement for NextFit {
+    const ROVING: bool = true;
+
+    fn rank(_: usize, _: usize) -> usize {
+        0
+    }
+}
+
+/// Places allocations in the smallest free block that fits, stopping early
+/// on an exact fit.
+///
+/// Every free block is ranked, but large free blocks are kept whole for
+/// as long as possible, which suits long running systems.
+pub struct BestFit;
+
+impl Placement for BestFit {
+    fn rank(capacity: usize, size: usize) -> usize {
+        capacity - size
+    }
+}
+
+/// Places allocations in the largest free block.
+///
+/// The memory left over after splitting is as large as possible,
+/// and so more likely to be usable by further allocations.
+pub struct WorstFit;
+
+impl Placement for WorstFit {
+    fn rank(capacity: usize, _: usize) -> usize {
+        usize::MAX - capacity
+    }
+}
@@ -0,0 +1,271 @@
+use super::{
+    block::{align_up, MemoryBlock, ALIGN},
+    Blocks, FirstFit, Fragmentation, MoreCore, Palloc, PallocError, PallocStats, Placement,
+};
+use core::{
+    alloc::Layout,
+    ptr::{null_mut, NonNull},
+};
+
+/// number of size classes, each one word larger than the previous
+const CLASSES: usize = 16;
+
+/// Allocator caching freed small blocks by size class, on top of a [`Palloc`].
+///
+/// Allocations up to [`MAX_CLASS_SIZE`](Self::MAX_CLASS_SIZE) bytes are rounded up
+/// to a multiple of the word size, their size class. Freed blocks are not given back
+/// to the underlying Palloc but pushed on the list of their class instead, so that
+/// further allocations of the same class are served from the list head in constant
+/// time. The lists are a cache in front of the Palloc rather than free lists carved
+/// out of the heap: when the list of a class is empty, the allocation goes through
+/// the Palloc and its search over the free blocks, as do larger or over-aligned ones.
+///
+/// Cached blocks keep their header, marked as cached, and the link to the next
+/// one is stored in their payload: no additional memory is needed.
+///
+/// # Fragmentation
+///
+/// Cached blocks are never merged with their free neighbours, and only serve
+/// allocations of their own class. [`flush`](#method.flush) is the only way for
+/// cached memory to merge back, by giving every cached block back to the Palloc:
+/// it is called by [`trim`](#method.trim) and [`shrink_to`](#method.shrink_to),
+/// and whenever the Palloc runs out of memory. Until then, workloads mixing
+/// sizes may leave the heap badly fragmented, which calling `flush` once the
+/// sizes in use change avoids.
+///
+/// # Safety
+/// See [`Palloc`]
+pub struct SegregatedPalloc<P = FirstFit> {
+    heap: Palloc<P>,
+    /// heap pointers of the cached blocks of every class, each
+    /// one holding the pointer to the following one
+    classes: [*mut u8; CLASSES],
+}
+
+impl SegregatedPalloc {
+    /// creates an empty allocator, see [`Palloc.empty`](crate::Palloc::empty)
+    pub const fn empty() -> SegregatedPalloc {
+        SegregatedPalloc::new()
+    }
+}
+
+impl<P: Placement> SegregatedPalloc<P> {
+    /// largest allocation served from the size class lists
+    pub const MAX_CLASS_SIZE: usize = CLASSES * ALIGN;
+
+    /// creates an empty allocator whose underlying Palloc uses the `P`
+    /// placement strategy, see [`Palloc.new`](crate::Palloc::new)
+    pub const fn new() -> SegregatedPalloc<P> {
+        SegregatedPalloc {
+            heap: Palloc::new(),
+            classes: [null_mut(); CLASSES],
+        }
+    }
+
+    /// ### Safety
+    /// See [`Palloc.init`](crate::Palloc::init)
+    pub unsafe fn init(&mut self, bottom: NonNull<u8>, size: usize) {
+        self.heap.init(bottom, size);
+        self.classes = [null_mut(); CLASSES];
+    }
+
+    /// ### Safety
+    /// See [`Palloc.add_region`](crate::Palloc::add_region)
+    pub unsafe fn add_region(&mut self, bottom: NonNull<u8>, size: usize) {
+        self.heap.add_region(bottom, size);
+    }
+
+    /// ### Safety
+    /// See [`Palloc.extend`](crate::Palloc::extend)
+    pub unsafe fn extend(&mut self, additional: usize) {
+        self.heap.extend(additional);
+    }
+
+    /// blocks of the heap, cached ones being reported as free,
+    /// see [`Palloc.blocks`](crate::Palloc::blocks)
+    pub fn blocks(&self) -> Blocks<'_> {
+        self.heap.blocks()
+    }
+
+    /// usage of the heap, cached blocks being reported as free,
+    /// see [`Palloc.stats`](crate::Palloc::stats)
+    pub fn stats(&self) -> PallocStats {
+        self.heap.stats()
+    }
+
+    /// fragmentation of the heap, cached blocks being reported as free,
+    /// see [`Palloc.fragmentation`](crate::Palloc::fragmentation)
+    pub fn fragmentation(&self) -> Fragmentation {
+        self.heap.fragmentation()
+    }
+
+    /// Flushes the size classes and gives back the free memory at the
+    /// end of the heap, see [`Palloc.trim`](crate::Palloc::trim)
+    pub fn trim(&mut self) -> usize {
+        self.flush();
+        self.heap.trim()
+    }
+
+    /// Flushes the size classes and shrinks the heap,
+    /// see [`Palloc.shrink_to`](crate::Palloc::shrink_to)
+    pub fn shrink_to(&mut self, size: usize) -> usize {
+        self.flush();
+        self.heap.shrink_to(size)
+    }
+
+    /// called before flushing the size classes once out of memory,
+    /// see [`Palloc.set_morecore`](crate::Palloc::set_morecore)
+    pub fn set_morecore(&mut self, morecore: &'static mut dyn MoreCore) {
+        self.heap.set_morecore(morecore);
+    }
+
+    /// ### Safety
+    /// See [`Palloc.init_from_slice`](crate::Palloc::init_from_slice)
+    pub unsafe fn init_from_slice(&mut self, heap: &mut [u8]) {
+        let bottom = NonNull::new(heap.as_mut_ptr()).expect("non nullpointed slice");
+        self.init(bottom, heap.len());
+    }
+
+    /// ### Safety
+    /// See [`Palloc.alloc`](crate::Palloc::alloc)
+    pub unsafe fn alloc(&mut self, size: usize) -> Result<NonNull<u8>, PallocError> {
+        let layout = Layout::from_size_align(size, 1).or(Err(PallocError::OutOfMemory))?;
+        self.alloc_layout(layout)
+    }
+
+    /// Allocates from the list of the size class of `layout` when it has a
+    /// cached block, from the underlying Palloc otherwise.
+    ///
+    /// ### Safety
+    /// See [`Palloc.alloc_layout`](crate::Palloc::alloc_layout)
+    pub unsafe fn alloc_layout(&mut self, layout: Layout) -> Result<NonNull<u8>, PallocError> {
+        let class = match class_of(layout.size()) {
+            Some(class) if layout.align() <= ALIGN => class,
+            _ => return self.alloc_heap(layout),
+        };
+
+        // blocks always have room for the largest size of their class
+        let Some(head) = NonNull::new(self.classes[class]) else {
+            return self.alloc_heap(layout);
+        };
+        self.classes[class] = *head.cast::<*mut u8>().as_ptr();
+
+        // the block may be larger than requested, it keeps track of the actual size
+        let block = MemoryBlock::from_heap_ptr(head).unwrap();
+        block.resize(layout.size());
+        self.heap.usage.add(layout.size());
+
+        Ok(head)
+    }
+
+    /// allocates from the underlying Palloc, flushing
+    /// the cached blocks when it runs out of memory
+    unsafe fn alloc_heap(&mut self, layout: Layout) -> Result<NonNull<u8>, PallocError> {
+        match self.heap.alloc_layout(layout) {
+            Err(PallocError::OutOfMemory) => {
+                self.flush();
+                self.heap.alloc_layout(layout)
+            }
+            result => result,
+        }
+    }
+
+    /// Resizes the allocation through the underlying Palloc, flushing the
+    /// cached blocks when it runs out of memory.
+    ///
+    /// ### Safety
+    /// See [`Palloc.realloc_layout`](crate::Palloc::realloc_layout)
+    pub unsafe fn realloc_layout(
+        &mut self,
+        alloc: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, PallocError> {
+        let block = MemoryBlock::from_heap_ptr(alloc).ok_or(PallocError::NullPtr)?;
+        if block.is_cached() {
+            return Err(PallocError::NotAllocated);
+        }
+
+        match self.heap.realloc_layout(alloc, layout) {
+            Err(PallocError::OutOfMemory) => {
+                self.flush();
+                self.heap.realloc_layout(alloc, layout)
+            }
+            result => result,
+        }
+    }
+
+    /// Deallocates memory at a given pointer location. Blocks belonging to a size
+    /// class are pushed on its list, all the others are freed in the underlying Palloc.
+    ///
+    /// ### Safety
+    /// See [`Palloc.free`](crate::Palloc::free)
+    pub unsafe fn free(&mut self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        let block = MemoryBlock::from_heap_ptr(alloc).ok_or(PallocError::NullPtr)?;
+        if !block.is_allocated() || block.is_cached() {
+            return Err(PallocError::DoubleFree {
+                addr: alloc.as_ptr() as usize,
+                size: block.size(),
+            });
+        }
+
+        // over-aligned allocations may not have room for the link
+        let class = class_of(block.size()).filter(|class| {
+            block
+                .max_size()
+                .is_some_and(|capacity| capacity >= class_size(*class))
+        });
+
+        match class {
+            None => self.heap.free(alloc),
+            Some(class) => {
+                self.heap.usage.sub(block.size());
+                block.set_cached(true);
+                *alloc.cast::<*mut u8>().as_ptr() = self.classes[class];
+                self.classes[class] = alloc.as_ptr();
+
+                Ok(())
+            }
+        }
+    }
+
+    /// deallocation used by the global allocators, checked
+    /// only when the `checked_free` feature is enabled.
+    pub(crate) unsafe fn global_free(&mut self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        self.heap.global_check(alloc)?;
+        self.free(alloc)
+    }
+
+    /// Gives every cached block back to the underlying Palloc,
+    /// where it is merged with its free neighbours.
+    pub fn flush(&mut self) {
+        for class in 0..CLASSES {
+            while let Some(head) = NonNull::new(self.classes[class]) {
+                unsafe {
+                    self.classes[class] = *head.cast::<*mut u8>().as_ptr();
+
+                    // cached blocks are always allocated, and not accounted as used
+                    let _ = self.heap.free(head);
+                }
+            }
+        }
+    }
+}
+
+/// size class of an allocation of `size` bytes, if small enough to have one
+fn class_of(size: usize) -> Option<usize> {
+    let class = align_up(size.max(1), ALIGN) / ALIGN - 1;
+    (class < CLASSES).then_some(class)
+}
+
+/// size of the blocks of `class`
+fn class_size(class: usize) -> usize {
+    (class + 1) * ALIGN
+}
+
+impl<P: Placement> Default for SegregatedPalloc<P> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+unsafe impl<P> Send for SegregatedPalloc<P> {}
@@ -0,0 +1,123 @@
+use core::cell::Cell;
+
+/// Snapshot of the usage of a heap, see [`Palloc.stats`](crate::Palloc::stats).
+///
+/// Bytes are accounted for at most once: `used`, `free` and `overhead` add
+/// up to the size of the heap, less the few bytes skipped to align the
+/// regions it is made of, like those before the first block of a region
+/// starting at an unaligned address.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct PallocStats {
+    /// bytes of the allocations in use, as requested
+    pub used: usize,
+    /// bytes of the free blocks, that new allocations may be placed in
+    pub free: usize,
+    /// bytes taken by block headers, along with the bytes allocated
+    /// blocks take beyond their size because of alignment
+    pub overhead: usize,
+    /// number of allocations in use
+    pub allocated_blocks: usize,
+    /// number of free blocks, the tail included
+    pub free_blocks: usize,
+    /// capacity of the largest free block
+    pub largest_free: usize,
+    /// highest `used` since the allocator has been initialized
+    pub peak: usize,
+}
+
+/// Number of buckets of the [`Fragmentation`] histogram, one
+/// for every power of two a block capacity may reach.
+const BUCKETS: usize = usize::BITS as usize;
+
+/// How usable the free memory of a heap is, computed from its free blocks,
+/// see [`Palloc.fragmentation`](crate::Palloc::fragmentation).
+///
+/// Plenty of free bytes spread over many small blocks cannot serve a large
+/// allocation: a low [`largest_free_ratio`](#method.largest_free_ratio), or
+/// a histogram emptying from its upper buckets, warns before running out
+/// of memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Fragmentation {
+    /// bytes of the free blocks
+    pub free: usize,
+    /// number of free blocks
+    pub free_blocks: usize,
+    /// capacity of the largest free block
+    pub largest_free: usize,
+    /// number of free blocks by capacity: bucket `i` counts the ones
+    /// of at least `2^i` bytes and less than `2^(i+1)`, bucket 0
+    /// counting empty blocks as well
+    pub histogram: [usize; BUCKETS],
+}
+
+impl Fragmentation {
+    /// fragmentation of a heap without free blocks
+    pub const fn new() -> Self {
+        Self {
+            free: 0,
+            free_blocks: 0,
+            largest_free: 0,
+            histogram: [0; BUCKETS],
+        }
+    }
+
+    /// accounts for a free block of `capacity` bytes
+    pub(crate) fn add(&mut self, capacity: usize) {
+        self.free += capacity;
+        self.free_blocks += 1;
+        self.largest_free = self.largest_free.max(capacity);
+        self.histogram[bucket(capacity)] += 1;
+    }
+
+    /// Share of the free bytes the largest free block holds, from 0 to 1:
+    /// the lower, the more fragmented. A heap without free bytes is
+    /// reported as unfragmented, with a ratio of 1.
+    pub fn largest_free_ratio(&self) -> f32 {
+        match self.free {
+            0 => 1.0,
+            free => self.largest_free as f32 / free as f32,
+        }
+    }
+}
+
+impl Default for Fragmentation {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// histogram bucket of a block of `capacity` bytes
+fn bucket(capacity: usize) -> usize {
+    capacity.checked_ilog2().unwrap_or(0) as usize
+}
+
+/// bytes in use and their peak, kept up to date on every
+/// allocation and deallocation
+pub(crate) struct Usage {
+    used: Cell<usize>,
+    peak: Cell<usize>,
+}
+
+impl Usage {
+    pub const fn new() -> Self {
+        Self {
+            used: Cell::new(0),
+            peak: Cell::new(0),
+        }
+    }
+
+    pub fn add(&self, size: usize) {
+        let used = self.used.get() + size;
+        self.used.set(used);
+        self.peak.set(self.peak.get().max(used));
+    }
+
+    pub fn sub(&self, size: usize) {
+        self.used.set(self.used.get() - size);
+    }
+
+    #[inline]
+    pub fn peak(&self) -> usize {
+        self.peak.get()
+    }
+}
@@ -0,0 +1,422 @@
+use super::{
+    block::{align_up, capacity_for, BlockIterator, MemoryBlock, ALIGN, MAX_SIZE, MIN_CAPACITY},
+    check_pointer,
+    free_list::{FreeLinks, FreeList},
+    stats::Usage,
+    Fragmentation, PallocError, PallocStats,
+};
+use core::{
+    alloc::Layout,
+    mem::size_of,
+    ptr::{copy_nonoverlapping, null_mut, NonNull},
+};
+
+/// log2 of the number of second level lists of every first level
+const SL_LOG: u32 = 4;
+const SL: usize = 1 << SL_LOG;
+
+/// log2 of the smallest size handled by the second first level. The first
+/// level below it is split linearly, one list for every multiple of [`ALIGN`].
+const FL_SHIFT: u32 = SL_LOG + ALIGN.trailing_zeros();
+const FL: usize = (usize::BITS - FL_SHIFT) as usize + 1;
+
+/// Two-Level Segregated Fit allocator, bounding the time taken by
+/// every allocation and deallocation.
+///
+/// Free blocks are sorted by capacity into lists, indexed by two levels:
+/// the first one by powers of two and the second one splitting each power
+/// of two into 16 equal ranges. Two bitmaps keep track of the lists that
+/// are not empty, so that finding a list only takes a couple of bit scans.
+///
+/// The requested size is rounded up to the next range before the search,
+/// so that any block of the found list fits and no list is ever walked:
+/// [`alloc_layout`](#method.alloc_layout) and [`free`](#method.free) take
+/// constant time, whatever the number of blocks in the heap. In exchange,
+/// a free block of the same range as the request may be skipped, and
+/// over-aligned allocations reserve enough space for the worst padding.
+///
+/// Blocks are laid out like the ones of [`Palloc`](crate::Palloc), free
+/// ones carrying the boundary tag used to merge them with their
+/// neighbours on free.
+///
+/// # Safety
+/// See [`Palloc`](crate::Palloc)
+pub struct TlsfPalloc {
+    bottom: *mut MemoryBlock,
+    size: usize,
+    /// first levels holding at least one free block
+    fl_bitmap: usize,
+    /// second levels holding at least one free block, for every first level
+    sl_bitmaps: [usize; FL],
+    lists: [[*mut MemoryBlock; SL]; FL],
+    usage: Usage,
+}
+
+impl TlsfPalloc {
+    /// creates an empty allocator, see [`Palloc.empty`](crate::Palloc::empty)
+    pub const fn empty() -> TlsfPalloc {
+        TlsfPalloc {
+            bottom: null_mut(),
+            size: 0,
+            fl_bitmap: 0,
+            sl_bitmaps: [0; FL],
+            lists: [[null_mut(); SL]; FL],
+            usage: Usage::new(),
+        }
+    }
+
+    /// Initializes the allocator with a pointer to a free heap region
+    /// and a size which defines the upper bound of the same.
+    ///
+    /// An empty allocated block is placed at the top of the region, so that
+    /// the whole region is a single free block bounded by it.
+    ///
+    /// ### Safety
+    /// See [`Palloc.init`](crate::Palloc::init). The region must also fit
+    /// the smallest free block along with the top block.
+    pub unsafe fn init(&mut self, bottom: NonNull<u8>, size: usize) {
+        let start = bottom.as_ptr() as usize;
+        let aligned = align_up(start, ALIGN);
+        let top = (start + size) & !(ALIGN - 1);
+        let header = size_of::<MemoryBlock>();
+
+        assert!(
+            top >= aligned + 2 * header + MIN_CAPACITY,
+            "heap region must fit a free block and the top block"
+        );
+
+        *self = Self::empty();
+        self.bottom = aligned as *mut MemoryBlock;
+        self.size = top - aligned;
+
+        let origin = MemoryBlock::default_from_ptr(NonNull::new_unchecked(self.bottom));
+        origin.insert_default(NonNull::new_unchecked((top - header) as *mut _));
+        origin.next_mut().unwrap().claim(0);
+
+        self.insert(origin);
+    }
+
+    /// Initializes heap from a memory slice. See [`init`](#method.init) for more informations.
+    ///
+    /// ### Safety
+    /// See [`init`](#method.init)
+    pub unsafe fn init_from_slice(&mut self, heap: &mut [u8]) {
+        let bottom = NonNull::new(heap.as_mut_ptr()).expect("non nullpointed slice");
+        self.init(bottom, heap.len());
+    }
+
+    /// ### Safety
+    /// See [`Palloc.alloc`](crate::Palloc::alloc)
+    pub unsafe fn alloc(&mut self, size: usize) -> Result<NonNull<u8>, PallocError> {
+        let layout = Layout::from_size_align(size, 1).or(Err(PallocError::OutOfMemory))?;
+        self.alloc_layout(layout)
+    }
+
+    /// Creates a new allocation fitting `layout` in constant time, splitting
+    /// the memory left unused off the chosen block.
+    ///
+    /// ### Safety
+    /// See [`Palloc.alloc_layout`](crate::Palloc::alloc_layout)
+    pub unsafe fn alloc_layout(&mut self, layout: Layout) -> Result<NonNull<u8>, PallocError> {
+        let capacity = capacity_for(layout.size());
+        let needed = match layout.align() <= ALIGN {
+            true => Some(capacity),
+            // room for the largest padding as well, see MemoryBlock::padding
+            false => capacity.checked_add(layout.align() + size_of::<MemoryBlock>() + MIN_CAPACITY),
+        }
+        .filter(|size| *size <= MAX_SIZE)
+        .ok_or(PallocError::OutOfMemory)?;
+
+        let (fl, sl) = self
+            .find(round_up(needed))
+            .ok_or(PallocError::OutOfMemory)?;
+        let block = &mut *self.lists[fl][sl];
+        self.remove(block);
+
+        let padding = block.padding(layout.align());
+        let block = match padding {
+            0 => block,
+            _ => {
+                let header = block.heap() as usize + padding - size_of::<MemoryBlock>();
+                block.insert_default(NonNull::new_unchecked(header as *mut _));
+                self.insert(block);
+                block.next_mut().unwrap()
+            }
+        };
+
+        let allocation = block.claim(layout.size());
+
+        // the following block is always allocated, no merging needed
+        let end = allocation as usize + capacity;
+        if end + size_of::<MemoryBlock>() + MIN_CAPACITY <= block.next().unwrap().address() {
+            block.insert_default(NonNull::new_unchecked(end as *mut _));
+            self.insert(block.next_mut().unwrap());
+        }
+
+        self.usage.add(layout.size());
+        Ok(NonNull::new_unchecked(allocation))
+    }
+
+    /// Moves the allocation to a new one fitting `layout`, preserving its content
+    /// up to the smaller of the two sizes, and frees it. Unlike [`Palloc`](crate::Palloc)
+    /// allocations are never resized in place, which keeps the time taken bounded
+    /// by the copy.
+    ///
+    /// ### Safety
+    /// See [`Palloc.realloc_layout`](crate::Palloc::realloc_layout)
+    pub unsafe fn realloc_layout(
+        &mut self,
+        alloc: NonNull<u8>,
+        layout: Layout,
+    ) -> Result<NonNull<u8>, PallocError> {
+        let block = MemoryBlock::from_heap_ptr(alloc).ok_or(PallocError::NullPtr)?;
+        if !block.is_allocated() {
+            return Err(PallocError::NotAllocated);
+        }
+
+        let old_size = block.size();
+        let moved = self.alloc_layout(layout)?;
+        copy_nonoverlapping(alloc.as_ptr(), moved.as_ptr(), old_size.min(layout.size()));
+        self.free(alloc)?;
+
+        Ok(moved)
+    }
+
+    /// Deallocates memory at a given pointer location in constant time, merging
+    /// it right away with its free neighbours.
+    ///
+    /// ### Safety
+    /// See [`Palloc.free`](crate::Palloc::free)
+    pub unsafe fn free(&mut self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        let mut block = MemoryBlock::from_heap_ptr(alloc).ok_or(PallocError::NullPtr)?;
+        if !block.is_allocated() {
+            return Err(PallocError::DoubleFree {
+                addr: alloc.as_ptr() as usize,
+                size: block.size(),
+            });
+        }
+
+        self.usage.sub(block.size());
+        block.dealloc()?;
+
+        // the top block is allocated, every free block is followed by another one
+        let next = block.next_mut().unwrap() as *mut MemoryBlock;
+        if !(*next).is_allocated() {
+            self.remove(&mut *next);
+            block.absorb_next();
+        }
+
+        if let Some(previous) = block.previous_free() {
+            self.remove(previous);
+            previous.absorb_next();
+            block = previous;
+        }
+
+        self.insert(block);
+        Ok(())
+    }
+
+    /// Deallocates memory like [`free`](#method.free), but validates `alloc`
+    /// first instead of trusting it, see [`Palloc.free_checked`](crate::Palloc::free_checked).
+    /// Validating the pointer takes linear time.
+    ///
+    /// ### Safety
+    /// Any pointer is accepted, but the block chain itself must not be corrupted.
+    pub unsafe fn free_checked(&mut self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        // an empty allocator has no block to point to
+        let origin = self.bottom.as_mut().ok_or(PallocError::ForeignPointer)?;
+        check_pointer(origin, self.size, alloc)?;
+        self.free(alloc)
+    }
+
+    /// deallocation used by the global allocators, checked
+    /// only when the `checked_free` feature is enabled.
+    pub(crate) unsafe fn global_free(&mut self, alloc: NonNull<u8>) -> Result<(), PallocError> {
+        match cfg!(feature = "checked_free") {
+            true => self.free_checked(alloc),
+            false => self.free(alloc),
+        }
+    }
+
+    /// Walks every block of the heap to report its usage, see
+    /// [`Palloc.stats`](crate::Palloc::stats). The top block
+    /// is accounted as overhead.
+    pub fn stats(&self) -> PallocStats {
+        let mut stats = PallocStats {
+            peak: self.usage.peak(),
+            ..Default::default()
+        };
+
+        let Some(origin) = NonNull::new(self.bottom) else {
+            return stats;
+        };
+
+        let header = size_of::<MemoryBlock>();
+        for block in BlockIterator::new(origin.as_ptr()) {
+            let Some(capacity) = block.max_size() else {
+                stats.overhead += header;
+                break;
+            };
+
+            if block.is_allocated() {
+                stats.used += block.size();
+                stats.overhead += header + capacity - block.size();
+                stats.allocated_blocks += 1;
+            } else {
+                stats.free += capacity;
+                stats.overhead += header;
+                stats.free_blocks += 1;
+                stats.largest_free = stats.largest_free.max(capacity);
+            }
+        }
+
+        stats
+    }
+
+    /// Walks the free blocks of the heap to report how fragmented it is, see
+    /// [`Palloc.fragmentation`](crate::Palloc::fragmentation). The top block
+    /// is left out, like it is from the free bytes of [`stats`](#method.stats).
+    pub fn fragmentation(&self) -> Fragmentation {
+        let mut fragmentation = Fragmentation::new();
+        let Some(origin) = NonNull::new(self.bottom) else {
+            return fragmentation;
+        };
+
+        for block in BlockIterator::new(origin.as_ptr()) {
+            match block.max_size() {
+                Some(capacity) if !block.is_allocated() => fragmentation.add(capacity),
+                Some(_) => {}
+                None => break,
+            }
+        }
+
+        fragmentation
+    }
+
+    /// first non-empty list at or above the `(fl, sl)` one
+    fn find(&self, (fl, sl): (usize, usize)) -> Option<(usize, usize)> {
+        if fl >= FL {
+            return None;
+        }
+
+        match self.sl_bitmaps[fl] & (usize::MAX << sl) {
+            0 => match self.fl_bitmap & (usize::MAX << (fl + 1)) {
+                0 => None,
+                fl_bitmap => {
+                    let fl = fl_bitmap.trailing_zeros() as usize;
+                    Some((fl, self.sl_bitmaps[fl].trailing_zeros() as usize))
+                }
+            },
+            sl_bitmap => Some((fl, sl_bitmap.trailing_zeros() as usize)),
+        }
+    }
+
+    /// pushes a free block on the list of its capacity and writes its boundary tag
+    unsafe fn insert(&mut self, block: &mut MemoryBlock) {
+        let (fl, sl) = mapping(block.max_size().unwrap());
+        let head = self.lists[fl][sl];
+        let block_ptr = block as *mut MemoryBlock;
+
+        *FreeList::links(block_ptr) = FreeLinks {
+            prev: null_mut(),
+            next: head,
+        };
+        if !head.is_null() {
+            FreeList::links(head).prev = block_ptr;
+        }
+
+        self.lists[fl][sl] = block_ptr;
+        self.fl_bitmap |= 1 << fl;
+        self.sl_bitmaps[fl] |= 1 << sl;
+        block.tag();
+    }
+
+    /// takes a free block off the list of its capacity and clears its boundary tag
+    unsafe fn remove(&mut self, block: &mut MemoryBlock) {
+        let (fl, sl) = mapping(block.max_size().unwrap());
+        let FreeLinks { prev, next } = *FreeList::links(block);
+
+        match prev.is_null() {
+            true => self.lists[fl][sl] = next,
+            false => FreeList::links(prev).next = next,
+        }
+        if !next.is_null() {
+            FreeList::links(next).prev = prev;
+        }
+
+        if self.lists[fl][sl].is_null() {
+            self.sl_bitmaps[fl] &= !(1 << sl);
+            if self.sl_bitmaps[fl] == 0 {
+                self.fl_bitmap &= !(1 << fl);
+            }
+        }
+        block.untag();
+    }
+}
+
+/// indexes of the list holding blocks of `capacity` bytes
+fn mapping(capacity: usize) -> (usize, usize) {
+    match capacity >> FL_SHIFT {
+        0 => (0, capacity / ALIGN),
+        _ => {
+            let log = usize::BITS - 1 - capacity.leading_zeros();
+            let sl = (capacity >> (log - SL_LOG)) ^ SL;
+            ((log - FL_SHIFT) as usize + 1, sl)
+        }
+    }
+}
+
+/// Indexes of the first list whose blocks all fit `size` bytes.
+/// The size is rounded up to the next range of its first level.
+fn round_up(size: usize) -> (usize, usize) {
+    match size >> FL_SHIFT {
+        0 => mapping(align_up(size, ALIGN)),
+        _ => {
+            let log = usize::BITS - 1 - size.leading_zeros();
+            mapping(size + (1 << (log - SL_LOG)) - 1)
+        }
+    }
+}
+
+#[cfg(test)]
+impl TlsfPalloc {
+    /// free blocks of the heap, asserting that each one is tagged
+    /// and in the list of its capacity, and that no other is
+    pub(crate) fn free_blocks(&self) -> usize {
+        use super::free_list::FreeIterator;
+
+        let mut free = 0;
+        for block in unsafe { &mut *self.bottom }.iter_mut() {
+            let Some(next) = block.next() else { break };
+            assert_eq!(next.is_prev_free(), !block.is_allocated());
+
+            if !block.is_allocated() {
+                let (fl, sl) = mapping(block.max_size().unwrap());
+                let mut list = FreeIterator::new(self.lists[fl][sl]);
+                assert!(list.any(|listed| listed.address() == block.address()));
+                free += 1;
+            }
+        }
+
+        let lists = self.lists.iter().flatten();
+        let listed: usize = lists.map(|head| FreeIterator::new(*head).count()).sum();
+        assert_eq!(listed, free);
+
+        free
+    }
+
+    /// free blocks in the list holding blocks of the capacity
+    /// of an allocation of `size` bytes
+    pub(crate) fn listed(&self, size: usize) -> usize {
+        let (fl, sl) = mapping(capacity_for(size));
+        super::free_list::FreeIterator::new(self.lists[fl][sl]).count()
+    }
+}
+
+impl Default for TlsfPalloc {
+    fn default() -> Self {
+        Self::empty()
+    }
+}
+
+unsafe impl Send for TlsfPalloc {}
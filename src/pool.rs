@@ -0,0 +1,299 @@
+use crate::{GlobalPalloc, PallocError, UnsafeCellPalloc};
+use core::{
+    alloc::Layout,
+    cell::Cell,
+    marker::PhantomData,
+    mem::align_of,
+    ops::Deref,
+    ptr::{null_mut, NonNull},
+};
+
+#[cfg(feature = "allocator_api")]
+use core::alloc::{AllocError, Allocator};
+
+/// Pool of fixed size slots, carved out of a single chunk of memory.
+///
+/// Every slot fits the layout the pool has been created with, and carries
+/// no header at all: free slots are linked together through their first
+/// word, so that allocating and freeing one only takes a couple of pointer
+/// updates. Slots never handed out are not linked in advance, they are
+/// taken in order from the end of the used part of the chunk instead.
+///
+/// The chunk is either a raw region, see [`from_region`](#method.from_region),
+/// or allocated from a [`GlobalPalloc`] on the first allocation. In the latter
+/// case it is given back as soon as every slot is free again, and when the
+/// pool is dropped. The allocator is only borrowed through a shared reference,
+/// so that several pools may take their chunks from the same heap, next to
+/// any other allocation: a plain [`Palloc`](crate::Palloc) is shared through
+/// an [`UnsafeCellPalloc`], the default.
+///
+/// Slots are handed out through `&self`, which also makes `&FixedPool`
+/// an [`Allocator`](core::alloc::Allocator) for layouts fitting a slot.
+pub struct FixedPool<'a, A: GlobalPalloc = UnsafeCellPalloc> {
+    parent: Option<&'a A>,
+    layout: Layout,
+    slots: usize,
+    chunk: Cell<*mut u8>,
+    /// first free slot, holding the address of the next one
+    free: Cell<*mut u8>,
+    /// slots handed out at least once since the chunk has been taken
+    touched: Cell<usize>,
+    used: Cell<usize>,
+}
+
+impl<'a, A: GlobalPalloc> FixedPool<'a, A> {
+    /// Creates a pool of `slots` slots fitting `layout`, whose chunk is
+    /// allocated from `allocator` once needed.
+    pub fn new(allocator: &'a A, layout: Layout, slots: usize) -> Self {
+        Self::with_chunk(Some(allocator), null_mut(), layout, slots)
+    }
+
+    fn with_chunk(parent: Option<&'a A>, chunk: *mut u8, layout: Layout, slots: usize) -> Self {
+        FixedPool {
+            parent,
+            layout: slot_layout(layout),
+            slots,
+            chunk: Cell::new(chunk),
+            free: Cell::new(null_mut()),
+            touched: Cell::new(0),
+            used: Cell::new(0),
+        }
+    }
+
+    /// Hands out a free slot, allocating the chunk first when needed.
+    /// Returns [`OutOfMemory`](PallocError::OutOfMemory) when every slot is in use.
+    pub fn alloc(&self) -> Result<NonNull<u8>, PallocError> {
+        let chunk = self.chunk()?;
+
+        let slot = match NonNull::new(self.free.get()) {
+            Some(slot) => {
+                self.free.set(unsafe { *slot.cast::<*mut u8>().as_ptr() });
+                slot
+            }
+            None if self.touched.get() < self.slots => {
+                let slot = unsafe { chunk.add(self.touched.get() * self.layout.size()) };
+                self.touched.set(self.touched.get() + 1);
+                unsafe { NonNull::new_unchecked(slot) }
+            }
+            None => return Err(PallocError::OutOfMemory),
+        };
+
+        self.used.set(self.used.get() + 1);
+        Ok(slot)
+    }
+
+    /// Gives a slot back to the pool. Once every slot is free, the chunk
+    /// is given back to the allocator it has been allocated from, if any.
+    ///
+    /// Pointers outside of the chunk return [`ForeignPointer`](PallocError::ForeignPointer)
+    /// and pointers within it not pointing to the start of a slot
+    /// [`InvalidPointer`](PallocError::InvalidPointer). Slots freed twice
+    /// cannot be told apart from allocated ones.
+    ///
+    /// ### Safety
+    /// The slot must not be used anymore, and must not be free already.
+    pub unsafe fn free(&self, slot: NonNull<u8>) -> Result<(), PallocError> {
+        self.check(slot)?;
+        self.push(slot);
+        Ok(())
+    }
+
+    /// succeeds when `slot` points to the start of a slot handed out
+    fn check(&self, slot: NonNull<u8>) -> Result<(), PallocError> {
+        let (chunk, address) = (self.chunk.get() as usize, slot.as_ptr() as usize);
+        let end = chunk + self.touched.get() * self.layout.size();
+        if chunk == 0 || address < chunk || address >= end {
+            return Err(PallocError::ForeignPointer);
+        }
+
+        match (address - chunk).is_multiple_of(self.layout.size()) {
+            true => Ok(()),
+            false => Err(PallocError::InvalidPointer),
+        }
+    }
+
+    /// links a checked slot back into the free slots
+    unsafe fn push(&self, slot: NonNull<u8>) {
+        *slot.cast::<*mut u8>().as_ptr() = self.free.get();
+        self.free.set(slot.as_ptr());
+        self.used.set(self.used.get() - 1);
+
+        if self.used.get() == 0 {
+            self.release();
+        }
+    }
+
+    /// layout of every slot, fitting the one the pool has been created with
+    #[inline]
+    pub fn slot_layout(&self) -> Layout {
+        self.layout
+    }
+
+    /// number of slots of the pool
+    #[inline]
+    pub fn capacity(&self) -> usize {
+        self.slots
+    }
+
+    /// number of slots in use
+    #[inline]
+    pub fn used(&self) -> usize {
+        self.used.get()
+    }
+
+    /// chunk of the slots, allocated from the parent allocator when missing
+    fn chunk(&self) -> Result<*mut u8, PallocError> {
+        if !self.chunk.get().is_null() {
+            return Ok(self.chunk.get());
+        }
+
+        let parent = self.parent.ok_or(PallocError::OutOfMemory)?;
+        // global allocators take no empty layouts
+        let size = self.layout.size().checked_mul(self.slots);
+        let layout = size
+            .filter(|size| *size > 0)
+            .and_then(|size| Layout::from_size_align(size, self.layout.align()).ok())
+            .ok_or(PallocError::OutOfMemory)?;
+
+        let chunk = unsafe { parent.alloc(layout) };
+        if chunk.is_null() {
+            return Err(PallocError::OutOfMemory);
+        }
+
+        self.chunk.set(chunk);
+        Ok(chunk)
+    }
+
+    /// gives the chunk back to the parent allocator, if any
+    fn release(&self) {
+        let (Some(parent), Some(chunk)) = (self.parent, NonNull::new(self.chunk.get())) else {
+            return;
+        };
+
+        // the chunk has been allocated from the parent and is freed only once
+        let _ = unsafe { parent.free(chunk) };
+        self.chunk.set(null_mut());
+        self.free.set(null_mut());
+        self.touched.set(0);
+    }
+}
+
+impl FixedPool<'static> {
+    /// Creates a pool over a raw region of `size` bytes, with as
+    /// many slots fitting `layout` as the region can hold.
+    ///
+    /// ### Safety
+    /// The whole region must be accessible and free to use,
+    /// for as long as the pool and its slots are.
+    pub unsafe fn from_region(region: NonNull<u8>, size: usize, layout: Layout) -> Self {
+        let layout = slot_layout(layout);
+        let start = region.as_ptr() as usize;
+        let offset = start.next_multiple_of(layout.align()) - start;
+        let slots = size.saturating_sub(offset) / layout.size();
+
+        Self::with_chunk(None, region.as_ptr().add(offset), layout, slots)
+    }
+}
+
+/// smallest layout fitting `layout` along with the link of a free slot
+fn slot_layout(layout: Layout) -> Layout {
+    let link = Layout::new::<*mut u8>();
+    let size = layout.size().max(link.size());
+    let align = layout.align().max(align_of::<*mut u8>());
+
+    Layout::from_size_align(size, align)
+        .expect("slot layout overflows")
+        .pad_to_align()
+}
+
+impl<A: GlobalPalloc> Drop for FixedPool<'_, A> {
+    fn drop(&mut self) {
+        if let (Some(parent), Some(chunk)) = (self.parent, NonNull::new(self.chunk.get())) {
+            let _ = unsafe { parent.free(chunk) };
+        }
+    }
+}
+
+#[cfg(feature = "allocator_api")]
+unsafe impl<A: GlobalPalloc> Allocator for FixedPool<'_, A> {
+    fn allocate(&self, layout: Layout) -> Result<NonNull<[u8]>, AllocError> {
+        if layout.size() > self.layout.size() || layout.align() > self.layout.align() {
+            return Err(AllocError);
+        }
+
+        self.alloc()
+            .map(|slot| NonNull::slice_from_raw_parts(slot, self.layout.size()))
+            .or(Err(AllocError))
+    }
+
+    unsafe fn deallocate(&self, ptr: NonNull<u8>, _layout: Layout) {
+        if let Err(err) = self.free(ptr) {
+            panic!("palloc: {}", err)
+        }
+    }
+}
+
+/// Pool of slots holding values of type `T`, see [`FixedPool`].
+///
+/// Values still in the pool when it is dropped are not dropped.
+pub struct Pool<'a, T, A: GlobalPalloc = UnsafeCellPalloc> {
+    pool: FixedPool<'a, A>,
+    value: PhantomData<T>,
+}
+
+impl<'a, T, A: GlobalPalloc> Pool<'a, T, A> {
+    /// Creates a pool of `slots` values, whose chunk is allocated
+    /// from `allocator` once needed. See [`FixedPool::new`].
+    pub fn new(allocator: &'a A, slots: usize) -> Self {
+        Pool {
+            pool: FixedPool::new(allocator, Layout::new::<T>(), slots),
+            value: PhantomData,
+        }
+    }
+
+    /// Moves `value` into a free slot, returning it back
+    /// when every slot is in use.
+    pub fn alloc(&self, value: T) -> Result<NonNull<T>, T> {
+        match self.pool.alloc() {
+            Ok(slot) => {
+                let slot = slot.cast::<T>();
+                unsafe { slot.as_ptr().write(value) };
+                Ok(slot)
+            }
+            Err(_) => Err(value),
+        }
+    }
+
+    /// Drops the value in `slot` and gives the slot back to the pool.
+    ///
+    /// ### Safety
+    /// See [`FixedPool::free`]. The value must not have been dropped already.
+    pub unsafe fn free(&self, slot: NonNull<T>) -> Result<(), PallocError> {
+        self.pool.check(slot.cast())?;
+        slot.as_ptr().drop_in_place();
+        self.pool.push(slot.cast());
+        Ok(())
+    }
+}
+
+impl<T> Pool<'static, T> {
+    /// Creates a pool over a raw region of `size` bytes.
+    /// See [`FixedPool::from_region`].
+    ///
+    /// ### Safety
+    /// See [`FixedPool::from_region`]
+    pub unsafe fn from_region(region: NonNull<u8>, size: usize) -> Self {
+        Pool {
+            pool: FixedPool::from_region(region, size, Layout::new::<T>()),
+            value: PhantomData,
+        }
+    }
+}
+
+impl<'a, T, A: GlobalPalloc> Deref for Pool<'a, T, A> {
+    type Target = FixedPool<'a, A>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.pool
+    }
+}
@@ -0,0 +1,138 @@
+extern crate std;
+
+use super::{shared_allocator, Heap};
+use crate::{Arena, PallocError};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    ptr::NonNull,
+};
+
+#[test]
+fn test_bump() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let allocator = shared_allocator(&mut heap.0);
+    let arena = Arena::new(&allocator, 256)?;
+
+    let first = arena.alloc(3)?;
+    let second = arena.alloc_layout(Layout::new::<u64>())?;
+    let third = arena.alloc(1)?;
+
+    // allocations follow each other, aligned as requested
+    assert_eq!(second.as_ptr() as usize, first.as_ptr() as usize + 8);
+    assert_eq!(third.as_ptr() as usize, second.as_ptr() as usize + 8);
+    assert_eq!(arena.used(), 17);
+
+    assert_eq!(arena.alloc(240).unwrap_err(), PallocError::OutOfMemory);
+    arena.alloc(239)?;
+    assert_eq!(arena.used(), arena.capacity());
+
+    Ok(())
+}
+
+#[test]
+fn test_mark_reset() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let allocator = shared_allocator(&mut heap.0);
+    let mut arena = Arena::new(&allocator, 256)?;
+
+    let kept = arena.alloc(16)?;
+    let mark = arena.mark();
+    let dropped = arena.alloc(32)?;
+    arena.alloc(64)?;
+
+    arena.reset_to(mark);
+    assert_eq!(arena.used(), 16);
+    assert_eq!(arena.alloc(8)?, dropped);
+
+    // marks past the current position are ignored
+    let late = arena.mark();
+    arena.reset();
+    arena.reset_to(late);
+    assert_eq!(arena.used(), 0);
+    assert_eq!(arena.alloc(1)?, kept);
+
+    Ok(())
+}
+
+#[test]
+#[should_panic(expected = "another arena")]
+fn test_foreign_mark() {
+    let mut heap = Heap([0; 1024]);
+    let mut other = Heap([0; 1024]);
+    let region = NonNull::new(other.0.as_mut_ptr()).unwrap();
+
+    let allocator = shared_allocator(&mut heap.0);
+    let mut arena = Arena::new(&allocator, 128).unwrap();
+    let foreign = unsafe { Arena::from_region(region, 1024) };
+    foreign.alloc(512).unwrap();
+
+    arena.reset_to(foreign.mark());
+}
+
+#[test]
+fn test_chunk_freed_on_drop() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let allocator = shared_allocator(&mut heap.0);
+
+    let arena = Arena::new(&allocator, 512)?;
+    arena.alloc(512)?;
+    drop(arena);
+
+    assert_eq!(allocator.stats().free_blocks, 1);
+    assert!(Arena::new(&allocator, 2048).is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_shared_heap() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let allocator = shared_allocator(&mut heap.0);
+    let mut arena = Arena::new(&allocator, 256)?;
+
+    // the heap is still usable while the arena lives
+    let layout = Layout::new::<[u64; 8]>();
+    let plain = unsafe { allocator.alloc(layout) };
+    assert!(!plain.is_null());
+    arena.alloc(200)?;
+    arena.reset();
+
+    unsafe { allocator.dealloc(plain, layout) };
+    drop(arena);
+    assert_eq!(allocator.stats().allocated_blocks, 0);
+
+    Ok(())
+}
+
+#[cfg(feature = "allocator_api")]
+#[test]
+fn test_allocator() -> Result<(), PallocError> {
+    use std::vec::Vec;
+
+    let mut heap = Heap([0; 1024]);
+    let allocator = shared_allocator(&mut heap.0);
+    let mut arena = Arena::new(&allocator, 512)?;
+
+    {
+        // the last allocation grows in place
+        let mut numbers = Vec::new_in(&arena);
+        numbers.extend(0..16u32);
+        assert_eq!(arena.used(), 64);
+
+        // others move to the top of the arena
+        let mut words: Vec<u64, _> = Vec::with_capacity_in(4, &arena);
+        words.push(1);
+        numbers.push(16);
+        assert_eq!(numbers.iter().sum::<u32>(), 16 * 17 / 2);
+        assert_eq!(arena.used(), 64 + 32 + 128);
+    }
+    // only the last allocation has been given back
+    assert_eq!(arena.used(), 96);
+
+    arena.reset();
+    let mut bytes = Vec::with_capacity_in(512, &arena);
+    bytes.resize(512, 1u8);
+    assert!(Vec::<u8, _>::try_with_capacity_in(1, &arena).is_err());
+
+    Ok(())
+}
@@ -0,0 +1,87 @@
+extern crate std;
+
+use super::{empty_backend, Heap};
+use crate::{BlockInfo, Palloc, PallocError, SegregatedPalloc};
+use core::{mem::size_of, ptr::NonNull};
+use std::vec::Vec;
+
+const HEADER: usize = 2 * size_of::<usize>();
+
+#[test]
+fn test_blocks() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = Palloc::empty();
+    assert_eq!(palloc.blocks().count(), 0);
+    unsafe { palloc.init_from_slice(&mut heap.0) };
+
+    let allocations = [40, 3, 100].map(|size| unsafe { palloc.alloc(size).unwrap() });
+    unsafe { palloc.free(allocations[1])? };
+
+    let blocks: Vec<BlockInfo> = palloc.blocks().collect();
+    let bottom = heap.0.as_ptr() as usize;
+    assert_eq!(
+        blocks[..2],
+        [
+            BlockInfo {
+                addr: bottom + HEADER,
+                header_addr: bottom,
+                size: 40,
+                capacity: 40,
+                allocated: true,
+            },
+            BlockInfo {
+                addr: allocations[1].as_ptr() as usize,
+                header_addr: allocations[1].as_ptr() as usize - HEADER,
+                size: 0,
+                capacity: 3 * size_of::<usize>(),
+                allocated: false,
+            },
+        ]
+    );
+    assert_eq!(blocks[2].addr, allocations[2].as_ptr() as usize);
+
+    // blocks follow each other up to the end of the heap
+    for pair in blocks.windows(2) {
+        assert_eq!(pair[0].addr + pair[0].capacity, pair[1].header_addr);
+    }
+    let tail = blocks.last().unwrap();
+    assert_eq!((blocks.len(), tail.allocated), (4, false));
+    assert_eq!(tail.addr + tail.capacity, bottom + 1024);
+
+    Ok(())
+}
+
+#[test]
+fn test_blocks_across_regions() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = Palloc::empty();
+    let base = heap.0.as_mut_ptr();
+
+    unsafe {
+        palloc.add_region(NonNull::new_unchecked(base.add(512)), 512);
+        palloc.alloc(64)?;
+        palloc.add_region(NonNull::new_unchecked(base), 256);
+        palloc.alloc(64)?;
+    }
+
+    // the fence closing the first region is not reported
+    let allocated = palloc.blocks().filter(|block| block.allocated).count();
+    assert_eq!((palloc.blocks().count(), allocated), (4, 2));
+
+    Ok(())
+}
+
+#[test]
+fn test_cached_blocks() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = empty_backend::<SegregatedPalloc>(&mut heap.0);
+
+    let cached = unsafe { palloc.alloc(16)? };
+    unsafe { palloc.free(cached)? };
+
+    let block = palloc.blocks().next().unwrap();
+    assert_eq!(block.addr, cached.as_ptr() as usize);
+    assert!(!block.allocated);
+
+    Ok(())
+}
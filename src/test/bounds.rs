@@ -0,0 +1,119 @@
+extern crate std;
+
+use crate::{Palloc, PallocError};
+use core::ptr::NonNull;
+use std::vec::Vec;
+
+const HEADER: usize = 2 * core::mem::size_of::<usize>();
+const CANARY: u8 = 0xA5;
+const CANARY_LEN: usize = 64;
+
+/// Heap region immediately followed by canary bytes. Palloc
+/// must never touch anything after the first `N` bytes.
+#[repr(C, align(16))]
+struct GuardedHeap<const N: usize> {
+    heap: [u8; N],
+    canary: [u8; CANARY_LEN],
+}
+
+impl<const N: usize> GuardedHeap<N> {
+    fn new() -> Self {
+        Self {
+            heap: [0; N],
+            canary: [CANARY; CANARY_LEN],
+        }
+    }
+
+    fn allocator(&mut self) -> Palloc {
+        let mut palloc = Palloc::empty();
+        unsafe { palloc.init_from_slice(&mut self.heap) };
+        palloc
+    }
+
+    fn assert_canary(&self) {
+        assert!(
+            self.canary.iter().all(|byte| *byte == CANARY),
+            "memory past the end of the heap has been written"
+        );
+    }
+}
+
+fn fill(ptr: NonNull<u8>, size: usize) {
+    unsafe { ptr.as_ptr().write_bytes(0xFF, size) };
+}
+
+#[test]
+fn test_exact_fill() -> Result<(), PallocError> {
+    let mut guarded = GuardedHeap::<128>::new();
+    let mut palloc = guarded.allocator();
+
+    // one header for the allocation and one for the tail that follows it
+    let size = 128 - 2 * HEADER;
+    let ptr = unsafe { palloc.alloc(size)? };
+    fill(ptr, size);
+
+    assert_eq!(
+        unsafe { palloc.alloc(0) }.unwrap_err(),
+        PallocError::OutOfMemory
+    );
+
+    guarded.assert_canary();
+    Ok(())
+}
+
+#[test]
+fn test_tail_header_out_of_bounds() {
+    let mut guarded = GuardedHeap::<128>::new();
+    let mut palloc = guarded.allocator();
+
+    // the allocation itself fits, the next tail header does not
+    let size = 128 - HEADER - 1;
+    assert_eq!(
+        unsafe { palloc.alloc(size) }.unwrap_err(),
+        PallocError::OutOfMemory
+    );
+
+    guarded.assert_canary();
+}
+
+#[test]
+fn test_fill_until_oom() {
+    for size in [1, 7, 8, 13, 24, 33] {
+        let mut guarded = GuardedHeap::<256>::new();
+        let mut palloc = guarded.allocator();
+
+        let mut allocations = Vec::new();
+        loop {
+            match unsafe { palloc.alloc(size) } {
+                Ok(ptr) => {
+                    fill(ptr, size);
+                    allocations.push(ptr);
+                }
+                Err(err) => {
+                    assert_eq!(err, PallocError::OutOfMemory);
+                    break;
+                }
+            }
+        }
+
+        assert!(!allocations.is_empty());
+        guarded.assert_canary();
+
+        for ptr in allocations {
+            unsafe { palloc.free(ptr) }.unwrap();
+        }
+
+        // everything got freed, the whole region is usable again
+        let ptr = unsafe { palloc.alloc(256 - 2 * HEADER) }.unwrap();
+        fill(ptr, 256 - 2 * HEADER);
+        guarded.assert_canary();
+    }
+}
+
+#[test]
+#[should_panic]
+fn test_region_too_small() {
+    let mut heap = [0u8; HEADER - 1];
+    let mut palloc = Palloc::empty();
+    unsafe { palloc.init_from_slice(&mut heap) };
+}
@@ -0,0 +1,191 @@
+use super::{empty_backend, Heap, Random};
+use crate::{BuddyPalloc, PallocError};
+use core::{alloc::Layout, ptr::NonNull};
+
+// heaps of 4096 bytes hold blocks of 128, 256, 512, 1024 and 2048
+// bytes, the order table taking the first 128 bytes
+const MIN_BLOCK: usize = BuddyPalloc::MIN_BLOCK;
+
+fn order(size: usize) -> usize {
+    size.trailing_zeros() as usize
+}
+
+#[test]
+fn test_split() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut buddy = empty_backend::<BuddyPalloc>(&mut heap.0);
+    let initial = buddy.free_blocks();
+    for size in [128, 256, 512, 1024, 2048] {
+        assert_eq!(initial[order(size)], 1);
+    }
+
+    // the 128 bytes block is split down to the smallest one
+    let first = unsafe { buddy.alloc(1)? };
+    let free = buddy.free_blocks();
+    assert_eq!(free[order(128)], 0);
+    for size in [MIN_BLOCK, 2 * MIN_BLOCK] {
+        assert_eq!(free[order(size)], 1);
+    }
+
+    // its buddy is handed out next
+    let second = unsafe { buddy.alloc(MIN_BLOCK)? };
+    assert_eq!(
+        second.as_ptr() as usize,
+        first.as_ptr() as usize ^ MIN_BLOCK
+    );
+    assert_eq!(buddy.free_blocks()[order(MIN_BLOCK)], 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_merge() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut buddy = empty_backend::<BuddyPalloc>(&mut heap.0);
+    let initial = buddy.free_blocks();
+
+    let allocations = [100, 8, 300, 8, 600].map(|size| unsafe { buddy.alloc(size) }.unwrap());
+    assert_eq!(
+        unsafe { buddy.alloc(4096) }.unwrap_err(),
+        PallocError::OutOfMemory
+    );
+
+    for index in [3, 0, 4, 1, 2] {
+        unsafe { buddy.free(allocations[index])? };
+    }
+
+    // everything merged back into the initial blocks
+    assert_eq!(buddy.free_blocks(), initial);
+    unsafe { buddy.alloc(2048)? };
+
+    Ok(())
+}
+
+#[test]
+fn test_alignment() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut buddy = empty_backend::<BuddyPalloc>(&mut heap.0);
+
+    for (size, align, block) in [
+        (100, 1, 128),
+        (8, 256, 256),
+        (3, 1, MIN_BLOCK),
+        (513, 8, 1024),
+    ] {
+        let layout = Layout::from_size_align(size, align).unwrap();
+        let ptr = unsafe { buddy.alloc_layout(layout)? };
+        assert!((ptr.as_ptr() as usize).is_multiple_of(block));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_free() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut buddy = empty_backend::<BuddyPalloc>(&mut heap.0);
+
+    let allocation = unsafe { buddy.alloc(100)? };
+    let mut foreign = 0u64;
+    let inner = unsafe { NonNull::new_unchecked(allocation.as_ptr().add(MIN_BLOCK)) };
+
+    assert_eq!(
+        unsafe { buddy.free(NonNull::from(&mut foreign).cast()) }.unwrap_err(),
+        PallocError::ForeignPointer
+    );
+    assert_eq!(
+        unsafe { buddy.free(inner) }.unwrap_err(),
+        PallocError::InvalidPointer
+    );
+
+    unsafe { buddy.free(allocation)? };
+    assert_eq!(
+        unsafe { buddy.free(allocation) }.unwrap_err(),
+        PallocError::DoubleFree {
+            addr: allocation.as_ptr() as usize,
+            size: 128
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_free_merged() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut buddy = empty_backend::<BuddyPalloc>(&mut heap.0);
+
+    let lower = unsafe { buddy.alloc(MIN_BLOCK)? };
+    let upper = unsafe { buddy.alloc(MIN_BLOCK)? };
+    unsafe {
+        buddy.free(upper)?;
+        buddy.free(lower)?;
+    }
+
+    // the upper buddy now points within the merged block
+    assert_eq!(
+        unsafe { buddy.free(upper) }.unwrap_err(),
+        PallocError::InvalidPointer
+    );
+    assert_eq!(
+        unsafe { buddy.free(lower) }.unwrap_err(),
+        PallocError::DoubleFree {
+            addr: lower.as_ptr() as usize,
+            size: 128
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_realloc() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut buddy = empty_backend::<BuddyPalloc>(&mut heap.0);
+
+    let allocation = unsafe { buddy.alloc(1000)? };
+    unsafe { allocation.as_ptr().write_bytes(0xAB, 64) };
+    let before = buddy.free_blocks();
+
+    // the upper halves are given back
+    let shrunk = unsafe { buddy.realloc_layout(allocation, Layout::new::<[u8; 64]>())? };
+    assert_eq!(shrunk, allocation);
+    let free = buddy.free_blocks();
+    for size in [64, 128, 256, 512] {
+        assert_eq!(free[order(size)], before[order(size)] + 1);
+    }
+
+    let grown = unsafe { buddy.realloc_layout(shrunk, Layout::new::<[u8; 200]>())? };
+    assert_ne!(grown, shrunk);
+    let content = unsafe { core::slice::from_raw_parts(grown.as_ptr(), 64) };
+    assert!(content.iter().all(|byte| *byte == 0xAB));
+
+    Ok(())
+}
+
+#[test]
+fn test_random_sequence() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut buddy = empty_backend::<BuddyPalloc>(&mut heap.0);
+    let initial = buddy.free_blocks();
+
+    let mut random = Random(0x1234_5678);
+
+    let mut allocations = [None; 16];
+    for _ in 0..2000 {
+        let slot = &mut allocations[random.below(16)];
+        match slot.take() {
+            Some(allocation) => unsafe { buddy.free(allocation)? },
+            None => *slot = unsafe { buddy.alloc(random.below(400)) }.ok(),
+        }
+
+        buddy.free_blocks();
+    }
+
+    for allocation in allocations.into_iter().flatten() {
+        unsafe { buddy.free(allocation)? };
+    }
+    assert_eq!(buddy.free_blocks(), initial);
+
+    Ok(())
+}
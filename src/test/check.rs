@@ -0,0 +1,149 @@
+use super::{empty_allocator, Heap};
+use crate::{HeapError, HeapErrorKind, Palloc, PallocError};
+use core::{mem::size_of, ptr::NonNull};
+
+const WORD: usize = size_of::<usize>();
+
+/// word `index` of the header of the block allocated at `alloc`
+unsafe fn header_word(alloc: NonNull<u8>, index: usize) -> *mut usize {
+    (alloc.as_ptr() as *mut usize).sub(2).add(index)
+}
+
+fn error_at(alloc: NonNull<u8>, kind: HeapErrorKind) -> Result<(), HeapError> {
+    Err(HeapError {
+        at: alloc.as_ptr() as usize - 2 * WORD,
+        kind,
+    })
+}
+
+#[test]
+fn test_healthy_heap() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = Palloc::empty();
+    assert_eq!(palloc.check(), Ok(()));
+    unsafe { palloc.init_from_slice(&mut heap.0) };
+
+    let allocations = [24, 100, 8, 60, 33].map(|size| unsafe { palloc.alloc(size).unwrap() });
+    unsafe {
+        palloc.free(allocations[1])?;
+        palloc.free(allocations[3])?;
+    }
+    assert_eq!(palloc.check(), Ok(()));
+
+    let mut second = Heap([0; 1024]);
+    unsafe {
+        palloc.add_region(NonNull::new_unchecked(second.0.as_mut_ptr()), 1024);
+        let large = palloc.alloc(900)?;
+        palloc.free(allocations[0])?;
+        assert_eq!(palloc.check(), Ok(()));
+
+        palloc.free(large)?;
+    }
+    assert_eq!(palloc.check(), Ok(()));
+
+    Ok(())
+}
+
+#[test]
+fn test_corrupted_link() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = empty_allocator(&mut heap.0);
+    let allocations = [0; 3].map(|_| unsafe { palloc.alloc(32).unwrap() });
+
+    unsafe {
+        let next = header_word(allocations[1], 1);
+        let original = *next;
+
+        *next = original + 1;
+        let kind = HeapErrorKind::InvalidLink { next: original + 1 };
+        assert_eq!(palloc.check(), error_at(allocations[1], kind));
+
+        *next = original + 4096;
+        let kind = HeapErrorKind::InvalidLink {
+            next: original + 4096,
+        };
+        assert_eq!(palloc.check(), error_at(allocations[1], kind));
+
+        *next = allocations[0].as_ptr() as usize - 2 * WORD;
+        let kind = HeapErrorKind::LinkNotIncreasing { next: *next };
+        assert_eq!(palloc.check(), error_at(allocations[1], kind));
+
+        *next = original;
+    }
+    assert_eq!(palloc.check(), Ok(()));
+
+    Ok(())
+}
+
+#[test]
+fn test_corrupted_size() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = empty_allocator(&mut heap.0);
+    let allocation = unsafe { palloc.alloc(32)? };
+
+    unsafe { *header_word(allocation, 0) += 100 };
+    let kind = HeapErrorKind::SizeOverflow {
+        size: 132,
+        capacity: 32,
+    };
+    assert_eq!(palloc.check(), error_at(allocation, kind));
+
+    let at = allocation.as_ptr() as usize - 2 * WORD;
+    let err = || Err(PallocError::HeapCorrupted { at });
+    assert_eq!(palloc.check().map_err(PallocError::from), err());
+    assert_eq!(unsafe { palloc.free(allocation) }, err());
+
+    Ok(())
+}
+
+#[test]
+fn test_corrupted_free_block() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = empty_allocator(&mut heap.0);
+    let allocations = [0; 3].map(|_| unsafe { palloc.alloc(32).unwrap() });
+    unsafe { palloc.free(allocations[1])? };
+
+    // the boundary tag, in the last word of the free block
+    unsafe {
+        let tag = allocations[1].as_ptr().add(32 - WORD) as *mut usize;
+        *tag = 0;
+        assert_eq!(
+            palloc.check(),
+            error_at(allocations[1], HeapErrorKind::BadTag)
+        );
+        *tag = allocations[1].as_ptr() as usize - 2 * WORD;
+    }
+    assert_eq!(palloc.check(), Ok(()));
+
+    // the free list links, in the first words of the free block
+    unsafe {
+        let next = (allocations[1].as_ptr() as *mut usize).add(1);
+        *next = allocations[2].as_ptr() as usize - 2 * WORD;
+        assert_eq!(
+            palloc.check(),
+            error_at(allocations[2], HeapErrorKind::FreeList)
+        );
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_allocated_tail() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = empty_allocator(&mut heap.0);
+    let allocation = unsafe { palloc.alloc(32)? };
+
+    // the flags of the tail, right after the allocation
+    let tail = unsafe { allocation.as_ptr().add(32) as *mut usize };
+    unsafe { *tail |= 1 << (usize::BITS - 1) };
+    assert_eq!(
+        palloc.check(),
+        Err(HeapError {
+            at: tail as usize,
+            kind: HeapErrorKind::MisplacedTail,
+        })
+    );
+
+    Ok(())
+}
@@ -0,0 +1,186 @@
+use super::{empty_allocator, Heap};
+use crate::{GlobalPalloc, Palloc, PallocError, SpinPalloc, UnsafeCellPalloc};
+use core::{
+    alloc::Layout,
+    mem::size_of,
+    ptr::NonNull,
+    sync::atomic::{AtomicUsize, Ordering},
+};
+
+const WORD: usize = size_of::<usize>();
+
+/// header address of the block allocated at `alloc`
+fn header(alloc: NonNull<u8>) -> usize {
+    alloc.as_ptr() as usize - 2 * WORD
+}
+
+fn corrupted(alloc: NonNull<u8>) -> PallocError {
+    PallocError::HeapCorrupted { at: header(alloc) }
+}
+
+/// six blocks of 32 bytes, the second and the fourth ones freed
+fn fragmented(heap: &mut Heap<1024>) -> (Palloc, [NonNull<u8>; 6]) {
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let allocations = [0; 6].map(|_| unsafe { palloc.alloc(32).unwrap() });
+    unsafe {
+        palloc.free(allocations[1]).unwrap();
+        palloc.free(allocations[3]).unwrap();
+    }
+
+    (palloc, allocations)
+}
+
+/// next free block link, the second word of the payload of a free block
+unsafe fn free_link(alloc: NonNull<u8>) -> *mut usize {
+    (alloc.as_ptr() as *mut usize).add(1)
+}
+
+#[test]
+fn test_free_list_loop() {
+    let mut heap = Heap([0; 1024]);
+    let (mut palloc, allocations) = fragmented(&mut heap);
+
+    // the last free block links back to the first one
+    unsafe { *free_link(allocations[3]) = header(allocations[1]) };
+    let err = unsafe { palloc.alloc(2048) }.unwrap_err();
+    assert_eq!(err, corrupted(allocations[3]));
+    assert!(palloc.check().is_err());
+}
+
+#[test]
+fn test_free_list_out_of_range() {
+    let mut heap = Heap([0; 1024]);
+    let (mut palloc, allocations) = fragmented(&mut heap);
+
+    unsafe { *free_link(allocations[1]) = 0x40 };
+    let err = unsafe { palloc.alloc(2048) }.unwrap_err();
+    assert_eq!(err, corrupted(allocations[1]));
+
+    unsafe { *free_link(allocations[1]) = header(allocations[3]) + 1 };
+    let err = unsafe { palloc.alloc(2048) }.unwrap_err();
+    assert_eq!(err, corrupted(allocations[1]));
+}
+
+#[test]
+fn test_corrupted_header_on_free() {
+    let mut heap = Heap([0; 1024]);
+    let (palloc, allocations) = fragmented(&mut heap);
+
+    let next = (header(allocations[4]) + WORD) as *mut usize;
+    let original = unsafe { *next };
+    unsafe { *next = usize::MAX & !(WORD - 1) };
+    let err = unsafe { palloc.free(allocations[4]) };
+    assert_eq!(err, Err(corrupted(allocations[4])));
+
+    // the block is left allocated
+    unsafe { *next = original };
+    assert_eq!(palloc.check(), Ok(()));
+    assert_eq!(unsafe { palloc.free(allocations[4]) }, Ok(()));
+}
+
+#[test]
+fn test_corrupted_tag_on_free() {
+    let mut heap = Heap([0; 1024]);
+    let (palloc, allocations) = fragmented(&mut heap);
+
+    // the boundary tag of the free block preceding the third one
+    let tag = unsafe { allocations[1].as_ptr().add(32 - WORD) as *mut usize };
+    unsafe { *tag = header(allocations[4]) };
+    let err = unsafe { palloc.free(allocations[2]) };
+    assert_eq!(err, Err(corrupted(allocations[2])));
+
+    unsafe { *tag = header(allocations[0]) };
+    let err = unsafe { palloc.free(allocations[2]) };
+    assert_eq!(err, Err(corrupted(allocations[0])));
+}
+
+#[test]
+fn test_misaligned_links() {
+    let mut heap = Heap([0; 1024]);
+    let (mut palloc, allocations) = fragmented(&mut heap);
+    let next = |alloc| (header(alloc) + WORD) as *mut usize;
+
+    // the header of the block being freed
+    let original = unsafe { *next(allocations[4]) };
+    unsafe { *next(allocations[4]) = original + 1 };
+    let err = unsafe { palloc.free(allocations[4]) };
+    assert_eq!(err, Err(corrupted(allocations[4])));
+    unsafe { *next(allocations[4]) = original };
+
+    // the header of the free block it is about to be merged with
+    let original = unsafe { *next(allocations[3]) };
+    unsafe { *next(allocations[3]) = original - 1 };
+    let err = unsafe { palloc.free(allocations[2]) };
+    assert_eq!(err, Err(corrupted(allocations[3])));
+    unsafe { *next(allocations[3]) = original };
+
+    // the boundary tag leading to the preceding free block
+    let tag = unsafe { allocations[1].as_ptr().add(32 - WORD) as *mut usize };
+    unsafe { *tag += 3 };
+    let err = unsafe { palloc.free(allocations[2]) };
+    assert_eq!(err, Err(corrupted(allocations[2])));
+    unsafe { *tag -= 3 };
+
+    // the free list link, followed by allocations
+    unsafe { *free_link(allocations[1]) = header(allocations[3]) + 5 };
+    let err = unsafe { palloc.alloc(2048) }.unwrap_err();
+    assert_eq!(err, corrupted(allocations[1]));
+    unsafe { *free_link(allocations[1]) = header(allocations[3]) };
+
+    assert_eq!(palloc.check(), Ok(()));
+}
+
+#[test]
+fn test_corrupted_tail() {
+    let mut heap = Heap([0; 1024]);
+    let (mut palloc, allocations) = fragmented(&mut heap);
+
+    // the tail header follows the last allocation
+    let tail = unsafe { allocations[5].as_ptr().add(32) as *mut usize };
+    unsafe { *tail.add(1) = tail as usize };
+    let err = unsafe { palloc.alloc(64) }.unwrap_err();
+    assert_eq!(err, PallocError::HeapCorrupted { at: tail as usize });
+}
+
+static CORRUPTED: AtomicUsize = AtomicUsize::new(0);
+
+fn on_corruption(at: usize) {
+    CORRUPTED.store(at, Ordering::SeqCst);
+}
+
+/// corrupts the free list of `allocator` and expects the handler to be
+/// called on allocation and deallocation, after the allocator is unlocked
+fn test_handler<A: GlobalPalloc>(mut allocator: A) {
+    let mut heap = Heap([0; 1024]);
+    unsafe { allocator.init_from_slice(&mut heap.0) };
+
+    let layout = Layout::from_size_align(32, 1).unwrap();
+    let allocations = [0; 3].map(|_| unsafe { NonNull::new(allocator.alloc(layout)).unwrap() });
+    unsafe {
+        allocator.dealloc(allocations[0].as_ptr(), layout);
+        *free_link(allocations[0]) = 0x40;
+    }
+
+    CORRUPTED.store(0, Ordering::SeqCst);
+    let large = Layout::from_size_align(2048, 1).unwrap();
+    assert!(unsafe { allocator.alloc(large) }.is_null());
+    assert_eq!(CORRUPTED.load(Ordering::SeqCst), header(allocations[0]));
+
+    // the header of the second allocation links out of the heap
+    CORRUPTED.store(0, Ordering::SeqCst);
+    unsafe { *((header(allocations[1]) + WORD) as *mut usize) = 0x40 };
+    let err = unsafe { allocator.free(allocations[1]) };
+    assert_eq!(err, Err(corrupted(allocations[1])));
+    assert_eq!(CORRUPTED.load(Ordering::SeqCst), header(allocations[1]));
+}
+
+#[test]
+fn test_spin_handler() {
+    test_handler(SpinPalloc::empty().with_corruption_handler(on_corruption));
+}
+
+#[test]
+fn test_unsafecell_handler() {
+    test_handler(UnsafeCellPalloc::empty().with_corruption_handler(on_corruption));
+}
@@ -0,0 +1,101 @@
+use super::{empty_backend, Heap};
+use crate::{
+    BuddyPalloc, Fragmentation, GlobalPalloc, Palloc, PallocError, PallocStats, SegregatedPalloc,
+    SpinPalloc, TlsfPalloc, UnsafeCellPalloc,
+};
+use core::{alloc::Layout, mem::size_of};
+
+const HEADER: usize = 2 * size_of::<usize>();
+
+/// free bytes, free blocks and largest free block, as reported by both
+fn assert_agrees(fragmentation: &Fragmentation, stats: &PallocStats) {
+    assert_eq!(fragmentation.free, stats.free);
+    assert_eq!(fragmentation.free_blocks, stats.free_blocks);
+    assert_eq!(fragmentation.largest_free, stats.largest_free);
+
+    let counted: usize = fragmentation.histogram.iter().sum();
+    assert_eq!(counted, fragmentation.free_blocks);
+}
+
+#[test]
+fn test_palloc_fragmentation() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut palloc = Palloc::empty();
+    assert_eq!(palloc.fragmentation(), Fragmentation::new());
+    assert_eq!(palloc.fragmentation().largest_free_ratio(), 1.0);
+    unsafe { palloc.init_from_slice(&mut heap.0) };
+
+    // a single free block, the tail
+    let fragmentation = palloc.fragmentation();
+    assert_eq!(fragmentation.free, 4096 - HEADER);
+    assert_eq!(fragmentation.histogram[11], 1);
+    assert_eq!(fragmentation.largest_free_ratio(), 1.0);
+
+    // every other block of 64 bytes freed
+    let allocations = [0; 16].map(|_| unsafe { palloc.alloc(64).unwrap() });
+    for allocation in allocations.iter().step_by(2) {
+        unsafe { palloc.free(*allocation)? };
+    }
+
+    let fragmentation = palloc.fragmentation();
+    let tail = 4096 - 16 * (64 + HEADER) - HEADER;
+    assert_eq!(fragmentation.histogram[6], 8);
+    assert_eq!(fragmentation.histogram[tail.ilog2() as usize], 1);
+    assert_eq!(fragmentation.free_blocks, 9);
+    assert_eq!(fragmentation.largest_free, tail);
+    assert_eq!(
+        fragmentation.largest_free_ratio(),
+        tail as f32 / (tail + 8 * 64) as f32
+    );
+    assert_agrees(&fragmentation, &palloc.stats());
+
+    Ok(())
+}
+
+#[test]
+fn test_backends_fragmentation() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut palloc = empty_backend::<SegregatedPalloc>(&mut heap.0);
+    let allocations = [24, 40, 300, 8].map(|size| unsafe { palloc.alloc(size).unwrap() });
+    unsafe {
+        palloc.free(allocations[0])?;
+        palloc.free(allocations[2])?;
+    }
+    assert_eq!(palloc.fragmentation().histogram[4], 1);
+    assert_agrees(&palloc.fragmentation(), &palloc.stats());
+
+    let mut heap = Heap([0; 4096]);
+    let mut tlsf = empty_backend::<TlsfPalloc>(&mut heap.0);
+    let allocations = [24, 40, 300, 8].map(|size| unsafe { tlsf.alloc(size).unwrap() });
+    unsafe { tlsf.free(allocations[2])? };
+    assert_agrees(&tlsf.fragmentation(), &tlsf.stats());
+
+    let mut heap = Heap([0; 4096]);
+    let mut buddy = empty_backend::<BuddyPalloc>(&mut heap.0);
+    let allocations = [24, 40, 300, 8].map(|size| unsafe { buddy.alloc(size).unwrap() });
+    unsafe { buddy.free(allocations[1])? };
+    assert_agrees(&buddy.fragmentation(), &buddy.stats());
+
+    Ok(())
+}
+
+fn wrapper_fragmentation<A: GlobalPalloc>(fragmentation: impl Fn(&A) -> Fragmentation) {
+    let mut heap = Heap([0; 4096]);
+    let mut allocator = A::new();
+    unsafe { allocator.init_from_slice(&mut heap.0) };
+
+    let layout = Layout::new::<[u64; 16]>();
+    let allocations = [0; 3].map(|_| unsafe { allocator.alloc(layout) });
+    unsafe { allocator.dealloc(allocations[1], layout) };
+
+    let fragmentation = fragmentation(&allocator);
+    assert_eq!(fragmentation.free_blocks, 2);
+    assert_eq!(fragmentation.histogram[7], 1);
+    assert!(fragmentation.largest_free_ratio() < 1.0);
+}
+
+#[test]
+fn test_wrapper_fragmentation() {
+    wrapper_fragmentation::<SpinPalloc>(SpinPalloc::fragmentation);
+    wrapper_fragmentation::<UnsafeCellPalloc<SegregatedPalloc>>(UnsafeCellPalloc::fragmentation);
+}
@@ -1,12 +1,13 @@
 extern crate std;
 
+use core::alloc::{GlobalAlloc, Layout};
 use std::{
     sync::Arc,
     thread::{self, JoinHandle},
     vec::Vec,
 };
 
-use crate::GlobalPalloc;
+use crate::{GlobalPalloc, PallocError};
 
 macro_rules! test_global_palloc {
     ($sec:tt, $alloc:ty, $($testfun:tt),+) => {
@@ -25,9 +26,71 @@ test_global_palloc!(
     spin,
     crate::SpinPalloc,
     test_vector_allocation,
+    test_aligned_layout,
+    test_zero_sized_layout,
+    test_double_free,
+    test_vector_grow_in_place,
+    test_concurrence
+);
+test_global_palloc!(
+    unsafecell,
+    crate::UnsafeCellPalloc,
+    test_vector_allocation,
+    test_aligned_layout,
+    test_zero_sized_layout,
+    test_double_free,
+    test_vector_grow_in_place
+);
+
+test_global_palloc!(
+    spin_best_fit,
+    crate::SpinPalloc<crate::Palloc<crate::BestFit>>,
+    test_vector_allocation,
+    test_vector_grow_in_place,
+    test_concurrence
+);
+test_global_palloc!(
+    unsafecell_next_fit,
+    crate::UnsafeCellPalloc<crate::Palloc<crate::NextFit>>,
+    test_vector_allocation,
+    test_aligned_layout,
+    test_double_free
+);
+
+test_global_palloc!(
+    spin_segregated,
+    crate::SpinPalloc<crate::SegregatedPalloc>,
+    test_vector_allocation,
+    test_aligned_layout,
+    test_zero_sized_layout,
+    test_double_free,
+    test_vector_grow_in_place,
+    test_concurrence
+);
+
+test_global_palloc!(
+    spin_tlsf,
+    crate::SpinPalloc<crate::TlsfPalloc>,
+    test_vector_allocation,
+    test_aligned_layout,
+    test_zero_sized_layout,
+    test_double_free,
+    test_concurrence
+);
+
+test_global_palloc!(
+    unsafecell_buddy,
+    crate::UnsafeCellPalloc<crate::BuddyPalloc>,
+    test_vector_allocation,
+    test_aligned_layout,
+    test_zero_sized_layout
+);
+test_global_palloc!(
+    spin_buddy,
+    crate::SpinPalloc<crate::BuddyPalloc>,
+    test_vector_allocation,
     test_concurrence
 );
-test_global_palloc!(unsafecell, crate::UnsafeCellPalloc, test_vector_allocation);
 
 fn test_vector_allocation<T: GlobalPalloc>() {
     let mut heap = std::vec![0u8; 200];
@@ -37,6 +100,75 @@ fn test_vector_allocation<T: GlobalPalloc>() {
     (0..20).for_each(|val| allocated.push(val));
 }
 
+fn test_aligned_layout<T: GlobalPalloc>() {
+    let mut heap = std::vec![0u8; 1024];
+    let allocator = unsafe { T::new_from_slice(&mut heap) };
+
+    for align in [16, 32, 256] {
+        let layout = Layout::from_size_align(8, align).unwrap();
+        let ptr = unsafe { GlobalAlloc::alloc(&allocator, layout) };
+
+        assert!(!ptr.is_null());
+        assert_eq!(ptr as usize % align, 0);
+        unsafe { GlobalAlloc::dealloc(&allocator, ptr, layout) };
+    }
+}
+
+fn test_zero_sized_layout<T: GlobalPalloc>() {
+    let mut heap = std::vec![0u8; 200];
+    let allocator = unsafe { T::new_from_slice(&mut heap) };
+
+    let layout = Layout::new::<()>();
+    let first = allocator.allocate(layout).unwrap();
+    let second = allocator.allocate(layout).unwrap();
+    assert_ne!(first, second);
+
+    unsafe {
+        allocator.deallocate(first.cast(), layout);
+        allocator.deallocate(second.cast(), layout);
+    }
+}
+
+fn test_double_free<T: GlobalPalloc>() {
+    let mut heap = std::vec![0u8; 200];
+    let allocator = unsafe { T::new_from_slice(&mut heap) };
+
+    let ptr = allocator
+        .allocate(Layout::new::<[u8; 12]>())
+        .unwrap()
+        .cast();
+    unsafe { allocator.free(ptr) }.unwrap();
+
+    assert_eq!(
+        unsafe { allocator.free(ptr) }.unwrap_err(),
+        PallocError::DoubleFree {
+            addr: ptr.as_ptr() as usize,
+            size: 12
+        }
+    );
+}
+
+fn test_vector_grow_in_place<T: GlobalPalloc>() {
+    let mut heap = std::vec![0u8; 400];
+    let allocator = unsafe { T::new_from_slice(&mut heap) };
+
+    let mut vector = Vec::<u8, &T>::with_capacity_in(4, &allocator);
+    let start = vector.as_ptr();
+
+    // the vector is the last allocation, it always grows into the tail
+    (0..200).for_each(|val| vector.push(val));
+    assert_eq!(vector.as_ptr(), start);
+
+    vector.truncate(10);
+    vector.shrink_to_fit();
+    assert_eq!(vector.as_ptr(), start);
+    assert!(vector.iter().copied().eq(0..10));
+
+    let ptr = unsafe { GlobalAlloc::alloc(&allocator, Layout::new::<[u8; 4]>()) };
+    let grown = unsafe { GlobalAlloc::realloc(&allocator, ptr, Layout::new::<[u8; 4]>(), 64) };
+    assert_eq!(ptr, grown);
+}
+
 fn test_concurrence<T: 'static + GlobalPalloc + Sync>() {
     let mut heap = std::vec![0u8; 500];
     let allocator = unsafe { T::new_from_slice(&mut heap) };
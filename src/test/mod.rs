@@ -1,4 +1,60 @@
 #![doc(hidden)]
 
+mod arena;
+mod blocks;
+mod bounds;
+mod buddy;
+mod check;
+mod corruption;
+mod fragmentation;
 mod global;
+mod morecore;
 mod palloc;
+mod placement;
+mod pool;
+mod regions;
+mod segregated;
+mod stats;
+mod tlsf;
+
+use crate::{GlobalPalloc, Palloc, PallocBackend, UnsafeCellPalloc};
+
+/// Heap for the tests, aligned to a page so that the addresses handed
+/// out do not depend on where it lies, whatever the allocator
+#[repr(C, align(4096))]
+struct Heap<const N: usize>([u8; N]);
+
+/// first fit allocator spanning the whole of `heap`
+fn empty_allocator(heap: &mut [u8]) -> Palloc {
+    empty_backend(heap)
+}
+
+/// allocator of any kind spanning the whole of `heap`
+fn empty_backend<A: PallocBackend>(heap: &mut [u8]) -> A {
+    let mut allocator = A::EMPTY;
+    unsafe { allocator.init_from_slice(heap) };
+    allocator
+}
+
+/// allocator spanning the whole of `heap`, shared through
+/// a reference with pools and arenas
+fn shared_allocator(heap: &mut [u8]) -> UnsafeCellPalloc {
+    let mut allocator = UnsafeCellPalloc::empty();
+    unsafe { allocator.init_from_slice(heap) };
+    allocator
+}
+
+/// Xorshift generator, for pseudo random sequences of allocations
+/// that are the same on every run
+struct Random(u32);
+
+impl Random {
+    /// next number of the sequence, below `bound`
+    fn below(&mut self, bound: u32) -> usize {
+        let Random(seed) = self;
+        *seed ^= *seed << 13;
+        *seed ^= *seed >> 17;
+        *seed ^= *seed << 5;
+        (*seed % bound) as usize
+    }
+}
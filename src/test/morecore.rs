@@ -0,0 +1,144 @@
+extern crate std;
+
+use super::{empty_allocator, Heap};
+use crate::{MoreCore, PallocError};
+use core::ptr::NonNull;
+use std::boxed::Box;
+
+/// hands out the memory of a buffer up to `limit`, a chunk at a time
+struct Chunks {
+    limit: usize,
+    chunk: usize,
+    calls: usize,
+}
+
+unsafe impl MoreCore for Chunks {
+    fn more_core(&mut self, top: NonNull<u8>, min: usize) -> Option<usize> {
+        self.calls += 1;
+        let additional = min.next_multiple_of(self.chunk);
+        (top.as_ptr() as usize + additional <= self.limit).then_some(additional)
+    }
+}
+
+#[test]
+fn test_extend() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = empty_allocator(&mut heap.0[..256]);
+
+    let first = unsafe { palloc.alloc(128)? };
+    assert_eq!(unsafe { palloc.alloc(300) }, Err(PallocError::OutOfMemory));
+
+    unsafe { palloc.extend(768) };
+    let second = unsafe { palloc.alloc(300)? };
+    assert!(second.as_ptr() > first.as_ptr());
+
+    // the tail still spans the rest of the buffer
+    unsafe { palloc.free(first)? };
+    unsafe { palloc.free(second)? };
+    assert_eq!(palloc.free_blocks(), 1);
+    assert!(unsafe { palloc.alloc(1024 - 32) }.is_ok());
+
+    Ok(())
+}
+
+#[test]
+fn test_morecore() -> Result<(), PallocError> {
+    let heap = Box::leak(Box::new(Heap([0; 1024])));
+    let limit = heap.0.as_ptr() as usize + 1024;
+    let chunks = Box::leak(Box::new(Chunks {
+        limit,
+        chunk: 128,
+        calls: 0,
+    }));
+    let calls = &chunks.calls as *const usize;
+
+    let mut palloc = empty_allocator(&mut heap.0[..128]);
+    palloc.set_morecore(chunks);
+
+    // free blocks are used first, the heap grows only when none fits
+    let first = unsafe { palloc.alloc(64)? };
+    unsafe { palloc.alloc(200)? };
+    assert_eq!(unsafe { *calls }, 1);
+    unsafe { palloc.free(first)? };
+    unsafe { palloc.alloc(64)? };
+    assert_eq!(unsafe { *calls }, 1);
+
+    // growing past the limit is reported once the morecore gives up
+    unsafe { palloc.alloc(400)? };
+    assert_eq!(unsafe { palloc.alloc(400) }, Err(PallocError::OutOfMemory));
+    assert_eq!(unsafe { *calls }, 3);
+
+    Ok(())
+}
+
+#[cfg(target_os = "linux")]
+mod mmap {
+    use super::Box;
+    use crate::{MoreCore, Palloc, PallocError};
+    use core::{ffi::c_void, ptr::NonNull};
+
+    const PROT_NONE: i32 = 0;
+    const PROT_READ_WRITE: i32 = 1 | 2;
+    const MAP_PRIVATE_ANONYMOUS: i32 = 0x02 | 0x20;
+    const MAP_NORESERVE: i32 = 0x4000;
+    const PAGE: usize = 4096;
+    const RESERVED: usize = 64 * PAGE;
+
+    extern "C" {
+        fn mmap(
+            addr: *mut c_void,
+            len: usize,
+            prot: i32,
+            flags: i32,
+            fd: i32,
+            off: i64,
+        ) -> *mut c_void;
+        fn mprotect(addr: *mut c_void, len: usize, prot: i32) -> i32;
+        fn munmap(addr: *mut c_void, len: usize) -> i32;
+    }
+
+    /// commits the pages of a reserved mapping as the heap grows
+    struct Reserved {
+        end: usize,
+    }
+
+    unsafe impl MoreCore for Reserved {
+        fn more_core(&mut self, top: NonNull<u8>, min: usize) -> Option<usize> {
+            let additional = min.next_multiple_of(PAGE);
+            if top.as_ptr() as usize + additional > self.end {
+                return None;
+            }
+
+            let committed = unsafe { mprotect(top.as_ptr().cast(), additional, PROT_READ_WRITE) };
+            (committed == 0).then_some(additional)
+        }
+    }
+
+    #[test]
+    fn test_mmap_morecore() -> Result<(), PallocError> {
+        let flags = MAP_PRIVATE_ANONYMOUS | MAP_NORESERVE;
+        let base = unsafe { mmap(core::ptr::null_mut(), RESERVED, PROT_NONE, flags, -1, 0) };
+        assert_ne!(base as isize, -1, "mmap failed");
+        assert_eq!(unsafe { mprotect(base, PAGE, PROT_READ_WRITE) }, 0);
+
+        let reserved = Box::leak(Box::new(Reserved {
+            end: base as usize + RESERVED,
+        }));
+        let mut palloc = Palloc::empty();
+        unsafe { palloc.init(NonNull::new(base.cast()).unwrap(), PAGE) };
+        palloc.set_morecore(reserved);
+
+        // every allocation is written to, committed pages only are accessible
+        let mut allocated = 0;
+        while let Ok(alloc) = unsafe { palloc.alloc(3000) } {
+            unsafe { alloc.as_ptr().write_bytes(0xAA, 3000) };
+            allocated += 1;
+        }
+
+        assert!(allocated > RESERVED / 3100, "{} allocations", allocated);
+        assert_eq!(unsafe { palloc.alloc(3000) }, Err(PallocError::OutOfMemory));
+
+        unsafe { munmap(base, RESERVED) };
+        Ok(())
+    }
+}
@@ -1,13 +1,12 @@
 extern crate std;
 
-use crate::{Palloc, PallocError};
-use core::ptr::{slice_from_raw_parts_mut, NonNull};
-
-fn empty_allocator(heap: &mut [u8]) -> Palloc {
-    let mut palloc = Palloc::empty();
-    unsafe { palloc.init_from_slice(heap) };
-    palloc
-}
+use super::{empty_allocator, empty_backend, Heap, Random};
+use crate::{BestFit, FirstFit, NextFit, Palloc, PallocError, Placement};
+use core::{
+    alloc::Layout,
+    mem::{align_of, size_of},
+    ptr::{slice_from_raw_parts_mut, NonNull},
+};
 
 fn memtest_allocation(start: NonNull<u8>, size: usize) -> bool {
     let memory = unsafe { &mut *slice_from_raw_parts_mut(start.as_ptr(), size) };
@@ -23,8 +22,9 @@ fn memtest_allocation(start: NonNull<u8>, size: usize) -> bool {
 
 #[test]
 fn test_single_alloc() -> Result<(), PallocError> {
-    let mut heap = [0u8; 50];
-    let mut palloc = empty_allocator(&mut heap);
+    // allocation header, 30 bytes rounded up and the following tail header
+    let mut heap = Heap([0u8; 64]);
+    let mut palloc = empty_allocator(&mut heap.0);
 
     let ptr = unsafe { palloc.alloc(30)? };
     assert!(memtest_allocation(ptr, 30), "should pass memtest");
@@ -89,3 +89,427 @@ fn test_oom() {
         PallocError::OutOfMemory
     );
 }
+
+#[test]
+fn test_aligned_alloc() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 512]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    for align in [64, 128] {
+        let layout = Layout::from_size_align(24, align).unwrap();
+        let ptr = unsafe { palloc.alloc_layout(layout)? };
+
+        assert_eq!(ptr.as_ptr() as usize % align, 0);
+        assert!(memtest_allocation(ptr, 24), "should pass memtest");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_alignment_padding_reused() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let layout = Layout::from_size_align(16, 64).unwrap();
+    let aligned = unsafe { palloc.alloc_layout(layout)? };
+    let padded = unsafe { palloc.alloc(16)? };
+
+    assert!(padded < aligned, "padding should be given back to the heap");
+
+    unsafe {
+        palloc.free(aligned)?;
+        palloc.free(padded)?;
+    }
+
+    Ok(())
+}
+
+fn is_word_aligned(ptr: NonNull<u8>) -> bool {
+    (ptr.as_ptr() as usize).is_multiple_of(align_of::<usize>())
+}
+
+#[test]
+fn test_odd_sizes_alignment() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 512]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let sizes = [13, 1, 7, 3, 29, 2, 17];
+    let mut allocations = [NonNull::dangling(); 7];
+    for (size, allocation) in sizes.iter().zip(allocations.iter_mut()) {
+        *allocation = unsafe { palloc.alloc(*size)? };
+        assert!(
+            is_word_aligned(*allocation),
+            "alloc({}) is misaligned",
+            size
+        );
+        assert!(
+            memtest_allocation(*allocation, *size),
+            "should pass memtest"
+        );
+    }
+
+    // free every other block and fill the holes with different odd sizes
+    for allocation in allocations.iter().step_by(2) {
+        unsafe { palloc.free(*allocation)? };
+    }
+
+    for size in [5, 9, 1, 11] {
+        let allocation = unsafe { palloc.alloc(size)? };
+        assert!(is_word_aligned(allocation), "alloc({}) is misaligned", size);
+        assert!(memtest_allocation(allocation, size), "should pass memtest");
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_unaligned_region() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_allocator(&mut heap.0[3..]);
+
+    for size in [3, 15, 1] {
+        let allocation = unsafe { palloc.alloc(size)? };
+        assert!(is_word_aligned(allocation), "alloc({}) is misaligned", size);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_zero_sized() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let first = unsafe { palloc.alloc(0)? };
+    let second = unsafe { palloc.alloc(0)? };
+    let sized = unsafe { palloc.alloc(8)? };
+
+    assert_ne!(first, second);
+    assert_ne!(first, sized, "zero sized block must not look free");
+    assert_ne!(second, sized, "zero sized block must not look free");
+
+    unsafe {
+        palloc.free(first)?;
+        palloc.free(second)?;
+        palloc.free(sized)?;
+    }
+
+    assert_eq!(
+        unsafe { palloc.free(first) }.unwrap_err(),
+        PallocError::DoubleFree {
+            addr: first.as_ptr() as usize,
+            size: 0
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_checked_free() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let allocation = unsafe { palloc.alloc(32)? };
+    let mut foreign = 0u64;
+    let foreign = NonNull::from(&mut foreign).cast();
+    let inner = unsafe { NonNull::new_unchecked(allocation.as_ptr().add(8)) };
+
+    assert_eq!(
+        unsafe { palloc.free_checked(foreign) }.unwrap_err(),
+        PallocError::ForeignPointer
+    );
+    assert_eq!(
+        unsafe { palloc.free_checked(inner) }.unwrap_err(),
+        PallocError::InvalidPointer
+    );
+
+    unsafe { palloc.free_checked(allocation)? };
+    assert_eq!(
+        unsafe { palloc.free_checked(allocation) }.unwrap_err(),
+        PallocError::DoubleFree {
+            addr: allocation.as_ptr() as usize,
+            size: 32
+        }
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_double_free_after_merge() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let first = unsafe { palloc.alloc(24)? };
+    let second = unsafe { palloc.alloc(40)? };
+    let _guard = unsafe { palloc.alloc(8)? };
+
+    unsafe {
+        palloc.free(first)?;
+        palloc.free(second)?;
+    }
+
+    // merges the two freed blocks, second's header is now free memory
+    let merged = unsafe { palloc.alloc(64)? };
+    assert_eq!(merged, first);
+    unsafe { palloc.free(merged)? };
+
+    let double_free = PallocError::DoubleFree {
+        addr: second.as_ptr() as usize,
+        size: 40,
+    };
+    assert_eq!(unsafe { palloc.free(second) }.unwrap_err(), double_free);
+    assert_eq!(
+        unsafe { palloc.free_checked(second) }.unwrap_err(),
+        double_free
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_realloc_grow_in_place() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let first = unsafe { palloc.alloc(16)? };
+    let second = unsafe { palloc.alloc(16)? };
+    let _guard = unsafe { palloc.alloc(8)? };
+    unsafe { palloc.free(second)? };
+
+    // absorbs the freed neighbour
+    assert_eq!(unsafe { palloc.realloc(first, 40)? }, first);
+    assert!(memtest_allocation(first, 40), "should pass memtest");
+
+    Ok(())
+}
+
+#[test]
+fn test_realloc_grow_tail() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let allocation = unsafe { palloc.alloc(16)? };
+    assert_eq!(unsafe { palloc.realloc(allocation, 200)? }, allocation);
+    assert!(memtest_allocation(allocation, 200), "should pass memtest");
+
+    // no room left for another tail past the allocation
+    assert_eq!(
+        unsafe { palloc.realloc(allocation, 256) }.unwrap_err(),
+        PallocError::OutOfMemory
+    );
+
+    // still valid and the tail is back in place
+    unsafe { palloc.free(allocation)? };
+    unsafe { palloc.alloc(200)? };
+
+    Ok(())
+}
+
+#[test]
+fn test_realloc_shrink() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let allocation = unsafe { palloc.alloc(96)? };
+    let _guard = unsafe { palloc.alloc(8)? };
+
+    assert_eq!(unsafe { palloc.realloc(allocation, 16)? }, allocation);
+
+    // the space given back lies right after the shrunk allocation
+    let split = unsafe { palloc.alloc(32)? };
+    assert!(split > allocation && (split.as_ptr() as usize) < allocation.as_ptr() as usize + 96);
+
+    Ok(())
+}
+
+#[test]
+fn test_realloc_move() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let allocation = unsafe { palloc.alloc(16)? };
+    let _guard = unsafe { palloc.alloc(8)? };
+
+    unsafe { allocation.as_ptr().write_bytes(0xAB, 16) };
+    let moved = unsafe { palloc.realloc(allocation, 64)? };
+
+    assert_ne!(moved, allocation);
+    let content = unsafe { core::slice::from_raw_parts(moved.as_ptr(), 16) };
+    assert!(content.iter().all(|byte| *byte == 0xAB));
+
+    // the old allocation has been freed
+    assert_eq!(unsafe { palloc.alloc(16)? }, allocation);
+
+    Ok(())
+}
+
+#[test]
+fn test_eager_coalescing() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 512]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let mut allocations = [NonNull::dangling(); 8];
+    for allocation in allocations.iter_mut() {
+        *allocation = unsafe { palloc.alloc(16)? };
+    }
+    let guard = unsafe { palloc.alloc(8)? };
+
+    // freed in a scattered order, every block is merged with its free
+    // neighbours right away: each run of freed allocations is a single
+    // free block, counted along with the tail
+    let frees = [
+        (1, 2),
+        (6, 3),
+        (3, 4),
+        (0, 4),
+        (7, 4),
+        (2, 3),
+        (4, 3),
+        (5, 2),
+    ];
+    for (index, free_blocks) in frees {
+        unsafe { palloc.free(allocations[index])? };
+        assert_eq!(palloc.free_blocks(), free_blocks);
+    }
+
+    // freeing the guard merges everything into the tail
+    unsafe { palloc.free(guard)? };
+    assert_eq!(palloc.free_blocks(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_coalescing_backwards() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let first = unsafe { palloc.alloc(16)? };
+    let second = unsafe { palloc.alloc(16)? };
+    let _guard = unsafe { palloc.alloc(8)? };
+
+    unsafe {
+        palloc.free(first)?;
+        palloc.free(second)?;
+    }
+
+    assert_eq!(palloc.free_blocks(), 2);
+    assert_eq!(unsafe { palloc.alloc(48)? }, first);
+
+    Ok(())
+}
+
+#[test]
+fn test_free_list() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let mut allocations = [NonNull::dangling(); 12];
+    for (index, allocation) in allocations.iter_mut().enumerate() {
+        *allocation = unsafe { palloc.alloc(8 * (index % 4 + 1))? };
+    }
+
+    // leaves four holes, listed apart from the tail
+    for index in [9, 1, 5, 3, 10, 6] {
+        unsafe { palloc.free(allocations[index])? };
+    }
+    assert_eq!(palloc.listed_blocks(), 4);
+    assert_eq!(palloc.listed_blocks(), palloc.free_blocks() - 1);
+
+    // the two holes large enough are taken off the list, unsplit
+    unsafe {
+        palloc.alloc(40)?;
+        palloc.alloc(40)?;
+    }
+    assert_eq!(palloc.listed_blocks(), 2);
+
+    for index in [0, 2, 4, 7, 8, 11] {
+        unsafe { palloc.free(allocations[index])? };
+    }
+    assert_eq!(palloc.listed_blocks(), palloc.free_blocks() - 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_boundary_tags() -> Result<(), PallocError> {
+    boundary_tags::<FirstFit>()?;
+    boundary_tags::<NextFit>()?;
+    boundary_tags::<BestFit>()
+}
+
+fn boundary_tags<P: Placement>() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 2048]);
+    let mut palloc = empty_backend::<Palloc<P>>(&mut heap.0);
+
+    // pseudo random sequence of allocations, reallocations and frees
+    let mut random = Random(0x2545_f491);
+
+    let mut allocations = [None; 16];
+    for _ in 0..2000 {
+        let slot = &mut allocations[random.below(16)];
+        match (slot.take(), random.below(4)) {
+            (Some(allocation), 0) => {
+                let resized = unsafe { palloc.realloc(allocation, random.below(128)) };
+                *slot = Some(resized.unwrap_or(allocation));
+            }
+            (Some(allocation), _) => unsafe { palloc.free(allocation)? },
+            (None, _) => {
+                let align = 8 << random.below(3);
+                let layout = Layout::from_size_align(random.below(96), align).unwrap();
+                *slot = unsafe { palloc.alloc_layout(layout) }.ok();
+            }
+        }
+
+        palloc.assert_tags();
+        assert_eq!(palloc.listed_blocks(), palloc.free_blocks() - 1);
+        assert_eq!(palloc.check(), Ok(()));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_trim() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = empty_allocator(&mut heap.0);
+    let header = 2 * size_of::<usize>();
+
+    let allocations = [0; 3].map(|_| unsafe { palloc.alloc(64).unwrap() });
+    unsafe { palloc.free(allocations[2])? };
+    unsafe { palloc.free(allocations[1])? };
+
+    // the heap now ends right after the tail header, following the first allocation
+    let end = allocations[0].as_ptr() as usize + 64 + header;
+    let top = heap.0.as_ptr() as usize + 1024;
+    assert_eq!(palloc.trim(), top - end);
+    assert_eq!(palloc.trim(), 0);
+    assert_eq!(unsafe { palloc.alloc(0) }, Err(PallocError::OutOfMemory));
+
+    unsafe { palloc.extend(128) };
+    unsafe { palloc.alloc(64)? };
+
+    Ok(())
+}
+
+#[test]
+fn test_shrink_to() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = empty_allocator(&mut heap.0);
+
+    let allocation = unsafe { palloc.alloc(64)? };
+    assert_eq!(palloc.shrink_to(512), 512);
+    assert_eq!(unsafe { palloc.alloc(500) }, Err(PallocError::OutOfMemory));
+    unsafe { palloc.alloc(300)? };
+
+    // allocated blocks are never released
+    unsafe { palloc.free(allocation)? };
+    let released = palloc.shrink_to(0);
+    assert!(released > 0 && released < 512 - 300);
+    assert_eq!(palloc.shrink_to(0), 0);
+
+    Ok(())
+}
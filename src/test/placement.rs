@@ -0,0 +1,89 @@
+extern crate std;
+
+use super::{empty_backend, Heap};
+use crate::{BestFit, FirstFit, NextFit, Palloc, PallocError, Placement, WorstFit};
+use core::ptr::NonNull;
+
+/// Leaves three holes of 32, 16 and 64 bytes, in this order,
+/// each followed by a guard allocation. Returns the holes.
+fn holed_allocator<P: Placement>(heap: &mut Heap<1024>) -> (Palloc<P>, [NonNull<u8>; 3]) {
+    let mut palloc = empty_backend::<Palloc<P>>(&mut heap.0);
+
+    let holes = [32, 16, 64].map(|size| {
+        let hole = unsafe { palloc.alloc(size) }.unwrap();
+        unsafe { palloc.alloc(8) }.unwrap();
+        hole
+    });
+
+    for hole in holes {
+        unsafe { palloc.free(hole) }.unwrap();
+    }
+
+    (palloc, holes)
+}
+
+#[test]
+fn test_first_fit() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let (mut palloc, holes) = holed_allocator::<FirstFit>(&mut heap);
+
+    assert_eq!(unsafe { palloc.alloc(16)? }, holes[0]);
+    assert_eq!(unsafe { palloc.alloc(40)? }, holes[2]);
+
+    Ok(())
+}
+
+#[test]
+fn test_best_fit() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let (mut palloc, holes) = holed_allocator::<BestFit>(&mut heap);
+
+    assert_eq!(unsafe { palloc.alloc(16)? }, holes[1]);
+    assert_eq!(unsafe { palloc.alloc(24)? }, holes[0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_worst_fit() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let (mut palloc, holes) = holed_allocator::<WorstFit>(&mut heap);
+
+    assert_eq!(unsafe { palloc.alloc(8)? }, holes[2]);
+
+    Ok(())
+}
+
+#[test]
+fn test_next_fit() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let (mut palloc, holes) = holed_allocator::<NextFit>(&mut heap);
+
+    // the search resumes after the last allocation, the guard
+    // following the 64 bytes hole, and wraps around to the first hole
+    assert_eq!(unsafe { palloc.alloc(16)? }, holes[0]);
+    assert_eq!(unsafe { palloc.alloc(8)? }, holes[1]);
+    assert_eq!(unsafe { palloc.alloc(8)? }, holes[2]);
+
+    Ok(())
+}
+
+#[test]
+fn test_next_fit_rover_freed() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = Palloc::<NextFit>::new();
+    unsafe { palloc.init_from_slice(&mut heap.0) };
+
+    let first = unsafe { palloc.alloc(16)? };
+    let second = unsafe { palloc.alloc(16)? };
+
+    // the block the search resumes from gets merged into its neighbours
+    unsafe {
+        palloc.free(first)?;
+        palloc.free(second)?;
+    }
+
+    assert_eq!(unsafe { palloc.alloc(48)? }, first);
+
+    Ok(())
+}
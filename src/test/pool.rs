@@ -0,0 +1,166 @@
+extern crate std;
+
+use super::{shared_allocator, Heap};
+use crate::{FixedPool, GlobalPalloc, PallocError, Pool, SpinPalloc};
+use core::{
+    alloc::{GlobalAlloc, Layout},
+    cell::Cell,
+    mem::forget,
+    ptr::NonNull,
+};
+
+#[test]
+fn test_slots() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let allocator = shared_allocator(&mut heap.0);
+    let pool = FixedPool::new(&allocator, Layout::new::<[u32; 3]>(), 4);
+    assert_eq!(pool.slot_layout(), Layout::new::<[u64; 2]>());
+
+    let slots = [0; 4].map(|_| pool.alloc().unwrap());
+    for pair in slots.windows(2) {
+        assert_eq!(pair[1].as_ptr() as usize - pair[0].as_ptr() as usize, 16);
+    }
+    assert_eq!(pool.alloc().unwrap_err(), PallocError::OutOfMemory);
+
+    // freed slots are handed out first, last freed first
+    unsafe {
+        pool.free(slots[1])?;
+        pool.free(slots[2])?;
+    }
+    assert_eq!(pool.alloc()?, slots[2]);
+    assert_eq!(pool.alloc()?, slots[1]);
+    assert_eq!(pool.used(), 4);
+
+    Ok(())
+}
+
+#[test]
+fn test_invalid_free() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let allocator = shared_allocator(&mut heap.0);
+    let pool = FixedPool::new(&allocator, Layout::new::<u64>(), 8);
+
+    let slot = pool.alloc()?;
+    let _other = pool.alloc()?;
+    let mut foreign = 0u64;
+    let inner = unsafe { NonNull::new_unchecked(slot.as_ptr().add(1)) };
+
+    assert_eq!(
+        unsafe { pool.free(NonNull::from(&mut foreign).cast()) }.unwrap_err(),
+        PallocError::ForeignPointer
+    );
+    assert_eq!(
+        unsafe { pool.free(inner) }.unwrap_err(),
+        PallocError::InvalidPointer
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_release_when_empty() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let allocator = shared_allocator(&mut heap.0);
+    let pool = FixedPool::new(&allocator, Layout::new::<u64>(), 16);
+
+    let slots = [0; 3].map(|_| pool.alloc().unwrap());
+    for slot in slots {
+        unsafe { pool.free(slot)? };
+    }
+
+    // the chunk has already been given back, not on drop
+    forget(pool);
+    assert_eq!(allocator.stats().free_blocks, 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_shared_heap() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut allocator = SpinPalloc::empty();
+    unsafe { allocator.init_from_slice(&mut heap.0) };
+
+    // two pools and a plain allocation, all from the same heap
+    let descriptors = Pool::<[u64; 4], _>::new(&allocator, 4);
+    let timers = Pool::<u32, _>::new(&allocator, 8);
+    let descriptor = descriptors.alloc([0; 4]).ok().unwrap();
+    let timer = timers.alloc(5).ok().unwrap();
+
+    let layout = Layout::new::<[u8; 64]>();
+    let plain = unsafe { allocator.alloc(layout) };
+    assert!(!plain.is_null());
+    assert_eq!(allocator.stats().allocated_blocks, 3);
+
+    unsafe {
+        allocator.dealloc(plain, layout);
+        descriptors.free(descriptor)?;
+        timers.free(timer)?;
+    }
+    assert_eq!(allocator.stats().allocated_blocks, 0);
+
+    Ok(())
+}
+
+#[test]
+fn test_typed_pool() -> Result<(), PallocError> {
+    struct Timer<'a>(u32, &'a Cell<u32>);
+
+    impl Drop for Timer<'_> {
+        fn drop(&mut self) {
+            self.1.set(self.1.get() + self.0);
+        }
+    }
+
+    let dropped = Cell::new(0);
+    let mut heap = Heap([0; 1024]);
+    let allocator = shared_allocator(&mut heap.0);
+    let pool = Pool::<Timer, _>::new(&allocator, 2);
+
+    let first = pool.alloc(Timer(1, &dropped)).ok().unwrap();
+    let second = pool.alloc(Timer(10, &dropped)).ok().unwrap();
+    let third = pool.alloc(Timer(100, &dropped));
+    assert!(third.is_err(), "the value must be given back");
+
+    unsafe {
+        assert_eq!(first.as_ref().0, 1);
+        pool.free(first)?;
+        pool.free(second)?;
+    }
+    drop(third);
+    assert_eq!(dropped.get(), 111);
+
+    Ok(())
+}
+
+#[test]
+fn test_region() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let region = NonNull::new(heap.0[4..].as_mut_ptr()).unwrap();
+    let pool = unsafe { Pool::<u64>::from_region(region, 1020) };
+
+    // the first slot is aligned, which leaves room for 127 of them
+    assert_eq!(pool.capacity(), 127);
+    let slot = pool.alloc(7).unwrap();
+    assert!((slot.as_ptr() as usize).is_multiple_of(8));
+
+    Ok(())
+}
+
+#[cfg(feature = "allocator_api")]
+#[test]
+fn test_allocator() {
+    use std::boxed::Box;
+
+    let mut heap = Heap([0; 1024]);
+    let allocator = shared_allocator(&mut heap.0);
+    let pool = FixedPool::new(&allocator, Layout::new::<[u64; 4]>(), 2);
+
+    let small = Box::new_in(5u8, &pool);
+    let fitting = Box::new_in([1u64; 4], &pool);
+    assert_eq!(pool.used(), 2);
+    assert!(Box::try_new_in([0u8; 64], &pool).is_err());
+
+    drop((small, fitting));
+    assert_eq!(pool.used(), 0);
+}
@@ -0,0 +1,114 @@
+use super::Heap;
+use crate::{Palloc, PallocError, SegregatedPalloc};
+use core::ptr::NonNull;
+
+/// allocator over two regions of 256 bytes, at `first` and `second`
+fn split_allocator(heap: &mut Heap<1024>, first: usize, second: usize) -> Palloc {
+    let mut palloc = Palloc::empty();
+    let base = heap.0.as_mut_ptr();
+    unsafe {
+        palloc.add_region(NonNull::new_unchecked(base.add(first)), 256);
+        palloc.add_region(NonNull::new_unchecked(base.add(second)), 256);
+    }
+
+    palloc
+}
+
+fn in_range(ptr: NonNull<u8>, heap: &Heap<1024>, start: usize, len: usize) -> bool {
+    let start = heap.0.as_ptr() as usize + start;
+    (start..start + len).contains(&(ptr.as_ptr() as usize))
+}
+
+#[test]
+fn test_all_regions_used() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = split_allocator(&mut heap, 256, 768);
+
+    // the free end of the first region is used once the tail moves to the second
+    let first = unsafe { palloc.alloc(128)? };
+    let second = unsafe { palloc.alloc(128)? };
+    let third = unsafe { palloc.alloc(64)? };
+    let fourth = unsafe { palloc.alloc(64)? };
+    assert!(unsafe { palloc.alloc(8) }.is_err());
+
+    assert!(in_range(first, &heap, 256, 256));
+    assert!(in_range(second, &heap, 768, 256));
+    assert!(in_range(third, &heap, 256, 256));
+    assert!(in_range(fourth, &heap, 768, 256));
+
+    Ok(())
+}
+
+#[test]
+fn test_no_merge_across_regions() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    // the second region lies right after the first one, in reverse order
+    let mut palloc = split_allocator(&mut heap, 256, 0);
+
+    let allocs = [0; 4].map(|_| unsafe { palloc.alloc(96).unwrap() });
+    for alloc in allocs {
+        unsafe { palloc.free(alloc)? };
+    }
+
+    // free memory is the free end of the first region and the tail
+    assert_eq!(palloc.free_blocks(), 2);
+    palloc.assert_tags();
+    assert_eq!(palloc.check(), Ok(()));
+    assert!(unsafe { palloc.alloc(300) }.is_err());
+
+    Ok(())
+}
+
+#[test]
+fn test_checked_free_regions() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = split_allocator(&mut heap, 256, 768);
+
+    let first = unsafe { palloc.alloc(200)? };
+    let second = unsafe { palloc.alloc(200)? };
+    let hole = NonNull::new(heap.0[600..].as_mut_ptr()).unwrap();
+    // the fence closing the first region, right at its end
+    let fence = NonNull::new(heap.0[512..].as_mut_ptr()).unwrap();
+
+    unsafe {
+        assert_eq!(palloc.free_checked(hole), Err(PallocError::ForeignPointer));
+        assert_eq!(palloc.free_checked(fence), Err(PallocError::ForeignPointer));
+        palloc.free_checked(second)?;
+        palloc.free_checked(first)?;
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_tiny_region_end() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = Palloc::empty();
+    let base = heap.0.as_mut_ptr();
+
+    unsafe {
+        palloc.add_region(NonNull::new_unchecked(base), 256);
+        // leaves no room for a free block before the fence
+        palloc.alloc(256 - 3 * 16)?;
+        palloc.add_region(NonNull::new_unchecked(base.add(512)), 512);
+        palloc.alloc(400)?;
+    }
+
+    assert_eq!(palloc.free_blocks(), 1);
+    Ok(())
+}
+
+#[test]
+fn test_segregated_regions() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 1024]);
+    let mut palloc = SegregatedPalloc::<crate::FirstFit>::new();
+    let base = heap.0.as_mut_ptr();
+
+    unsafe {
+        palloc.init(NonNull::new_unchecked(base), 128);
+        palloc.add_region(NonNull::new_unchecked(base.add(512)), 512);
+        palloc.alloc(400)?;
+    }
+
+    Ok(())
+}
@@ -0,0 +1,89 @@
+use super::{empty_backend, Heap};
+use crate::{PallocError, SegregatedPalloc};
+use core::{alloc::Layout, mem::size_of, ptr::NonNull};
+
+#[test]
+fn test_same_class_reused() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_backend::<SegregatedPalloc>(&mut heap.0);
+
+    let first = unsafe { palloc.alloc(12)? };
+    let guard = unsafe { palloc.alloc(12)? };
+    unsafe { palloc.free(first)? };
+
+    // 12 and 16 bytes share the same class
+    assert_eq!(unsafe { palloc.alloc(16)? }, first);
+    assert_ne!(unsafe { palloc.alloc(12)? }, guard);
+
+    Ok(())
+}
+
+#[test]
+fn test_other_class_not_reused() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_backend::<SegregatedPalloc>(&mut heap.0);
+
+    let first = unsafe { palloc.alloc(32)? };
+    unsafe { palloc.alloc(8)? };
+    unsafe { palloc.free(first)? };
+
+    // the cached block would fit, but is kept for its own class
+    assert_ne!(unsafe { palloc.alloc(8)? }, first);
+    assert_eq!(unsafe { palloc.alloc(32)? }, first);
+
+    Ok(())
+}
+
+#[test]
+fn test_cached_double_free() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_backend::<SegregatedPalloc>(&mut heap.0);
+
+    let allocation = unsafe { palloc.alloc(20)? };
+    unsafe { palloc.free(allocation)? };
+
+    assert_eq!(
+        unsafe { palloc.free(allocation) },
+        Err(PallocError::DoubleFree {
+            addr: allocation.as_ptr() as usize,
+            size: 20
+        })
+    );
+    assert_eq!(
+        unsafe { palloc.realloc_layout(allocation, Layout::new::<[u8; 40]>()) },
+        Err(PallocError::NotAllocated)
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_flush_on_oom() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_backend::<SegregatedPalloc>(&mut heap.0);
+
+    let allocations: [NonNull<u8>; 4] = [0; 4].map(|_| unsafe { palloc.alloc(40) }.unwrap());
+    allocations
+        .iter()
+        .try_for_each(|allocation| unsafe { palloc.free(*allocation) })?;
+
+    // only fits once the cached blocks are merged back together
+    let large = unsafe { palloc.alloc(200)? };
+    assert_eq!(large, allocations[0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_trim_flushes() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 256]);
+    let mut palloc = empty_backend::<SegregatedPalloc>(&mut heap.0);
+
+    let allocation = unsafe { palloc.alloc(40)? };
+    unsafe { palloc.free(allocation)? };
+
+    // the cached block is given back first, leaving the whole heap free
+    assert_eq!(palloc.trim(), 256 - 2 * size_of::<usize>());
+
+    Ok(())
+}
@@ -0,0 +1,147 @@
+use super::{empty_backend, Heap};
+use crate::{
+    BuddyPalloc, GlobalPalloc, Palloc, PallocError, PallocStats, SegregatedPalloc, SpinPalloc,
+    TlsfPalloc, UnsafeCellPalloc,
+};
+use core::{alloc::Layout, mem::size_of};
+
+const HEADER: usize = 2 * size_of::<usize>();
+
+fn total(stats: &PallocStats) -> usize {
+    stats.used + stats.free + stats.overhead
+}
+
+#[test]
+fn test_palloc_stats() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut palloc = Palloc::empty();
+    assert_eq!(palloc.stats(), PallocStats::default());
+    unsafe { palloc.init_from_slice(&mut heap.0) };
+
+    let allocations = [100, 30, 200, 64].map(|size| unsafe { palloc.alloc(size).unwrap() });
+    let stats = palloc.stats();
+    assert_eq!(stats.used, 394);
+    assert_eq!(stats.allocated_blocks, 4);
+    assert_eq!((stats.free_blocks, stats.largest_free), (1, stats.free));
+    assert_eq!(total(&stats), 4096);
+
+    unsafe {
+        palloc.free(allocations[1])?;
+        palloc.free(allocations[3])?;
+    }
+    let stats = palloc.stats();
+    assert_eq!((stats.used, stats.peak), (300, 394));
+    assert_eq!((stats.allocated_blocks, stats.free_blocks), (2, 2));
+    assert_eq!(stats.overhead, 4 * HEADER + 4);
+    assert_eq!(total(&stats), 4096);
+
+    // resizing in place is accounted too
+    let grown = unsafe { palloc.realloc(allocations[2], 1000)? };
+    assert_eq!(grown, allocations[2]);
+    assert_eq!(palloc.stats().peak, 1100);
+
+    Ok(())
+}
+
+#[test]
+fn test_regions_stats() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut palloc = Palloc::empty();
+    let base = heap.0.as_mut_ptr();
+
+    unsafe {
+        palloc.add_region(core::ptr::NonNull::new_unchecked(base.add(2048)), 2048);
+        palloc.alloc(512)?;
+        palloc.add_region(core::ptr::NonNull::new_unchecked(base), 1024);
+    }
+
+    // the fence is accounted as overhead, up to its own end
+    let stats = palloc.stats();
+    assert_eq!(stats.used, 512);
+    assert_eq!((stats.allocated_blocks, stats.free_blocks), (1, 2));
+    assert_eq!(stats.largest_free, 2048 - 512 - 3 * HEADER);
+    assert_eq!(total(&stats), 1024 + 2048);
+
+    Ok(())
+}
+
+#[test]
+fn test_segregated_stats() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut palloc = empty_backend::<SegregatedPalloc>(&mut heap.0);
+
+    let cached = unsafe { palloc.alloc(40)? };
+    unsafe { palloc.alloc(8)? };
+    unsafe { palloc.free(cached)? };
+
+    // the cached block is free, until handed out again
+    let stats = palloc.stats();
+    assert_eq!((stats.used, stats.peak), (8, 48));
+    assert_eq!((stats.allocated_blocks, stats.free_blocks), (1, 2));
+
+    unsafe { palloc.alloc(33)? };
+    assert_eq!(palloc.stats().used, 41);
+    palloc.flush();
+    assert_eq!(palloc.stats().used, 41);
+    assert_eq!(total(&palloc.stats()), 4096);
+
+    Ok(())
+}
+
+#[test]
+fn test_tlsf_stats() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut tlsf = empty_backend::<TlsfPalloc>(&mut heap.0);
+
+    let first = unsafe { tlsf.alloc(100)? };
+    unsafe { tlsf.alloc(20)? };
+    unsafe { tlsf.free(first)? };
+
+    let stats = tlsf.stats();
+    assert_eq!((stats.used, stats.peak), (20, 120));
+    assert_eq!((stats.allocated_blocks, stats.free_blocks), (1, 2));
+    assert_eq!(total(&stats), 4096);
+
+    Ok(())
+}
+
+#[test]
+fn test_buddy_stats() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 4096]);
+    let mut buddy = empty_backend::<BuddyPalloc>(&mut heap.0);
+
+    // whole blocks are accounted as used
+    let first = unsafe { buddy.alloc(100)? };
+    unsafe { buddy.alloc(200)? };
+    unsafe { buddy.free(first)? };
+
+    let stats = buddy.stats();
+    assert_eq!((stats.used, stats.peak), (256, 384));
+    assert_eq!(stats.largest_free, 2048);
+    assert_eq!(stats.overhead, 128);
+    assert_eq!(total(&stats), 4096);
+
+    Ok(())
+}
+
+fn wrapper_stats<A: GlobalPalloc>(stats: impl Fn(&A) -> PallocStats) {
+    let mut heap = Heap([0; 4096]);
+    let mut allocator = A::new();
+    unsafe { allocator.init_from_slice(&mut heap.0) };
+
+    let layout = Layout::new::<[u64; 8]>();
+    let allocations = [0; 2].map(|_| unsafe { allocator.alloc(layout) });
+    assert_eq!(stats(&allocator).used, 2 * 64);
+    assert_eq!(stats(&allocator).allocated_blocks, 2);
+
+    for allocation in allocations {
+        unsafe { allocator.dealloc(allocation, layout) };
+    }
+    assert_eq!((stats(&allocator).used, stats(&allocator).peak), (0, 128));
+}
+
+#[test]
+fn test_wrapper_stats() {
+    wrapper_stats::<SpinPalloc>(SpinPalloc::stats);
+    wrapper_stats::<UnsafeCellPalloc<SegregatedPalloc>>(UnsafeCellPalloc::stats);
+}
@@ -0,0 +1,185 @@
+use super::{empty_backend, Heap, Random};
+use crate::{PallocError, TlsfPalloc};
+use core::{alloc::Layout, mem::size_of, ptr::NonNull};
+
+const HEADER: usize = 2 * size_of::<usize>();
+
+#[test]
+fn test_reuse() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 8192]);
+    let mut tlsf = empty_backend::<TlsfPalloc>(&mut heap.0[..512]);
+
+    let first = unsafe { tlsf.alloc(40)? };
+    let _guard = unsafe { tlsf.alloc(8)? };
+    unsafe { tlsf.free(first)? };
+
+    assert_eq!(unsafe { tlsf.alloc(40)? }, first);
+    assert_eq!(tlsf.free_blocks(), 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_coalescing() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 8192]);
+    let mut tlsf = empty_backend::<TlsfPalloc>(&mut heap.0[..1024]);
+
+    let mut allocations = [NonNull::dangling(); 6];
+    for allocation in allocations.iter_mut() {
+        *allocation = unsafe { tlsf.alloc(48)? };
+    }
+
+    // merged with the following block, the preceding one, then both
+    for (index, free_blocks) in [(2, 2), (1, 2), (4, 3), (3, 2), (0, 2), (5, 1)] {
+        unsafe { tlsf.free(allocations[index])? };
+        assert_eq!(tlsf.free_blocks(), free_blocks);
+    }
+
+    // a single free block spanning the whole region again
+    assert_eq!(unsafe { tlsf.alloc(1024 - 4 * HEADER)? }, allocations[0]);
+
+    Ok(())
+}
+
+#[test]
+fn test_good_fit() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 8192]);
+    let mut tlsf = empty_backend::<TlsfPalloc>(&mut heap.0[..8192]);
+
+    let hole = unsafe { tlsf.alloc(1040)? };
+    let _guard = unsafe { tlsf.alloc(8)? };
+    unsafe { tlsf.free(hole)? };
+
+    // the hole would fit, but blocks of its range are not all large
+    // enough, and lists are never walked in search of one that is
+    assert_ne!(unsafe { tlsf.alloc(1032)? }, hole);
+    assert_eq!(unsafe { tlsf.alloc(1024)? }, hole);
+
+    Ok(())
+}
+
+#[test]
+fn test_constant_time() -> Result<(), PallocError> {
+    for holes in [1, 8, 32, 64] {
+        let mut heap = Heap([0; 8192]);
+        let mut tlsf = empty_backend::<TlsfPalloc>(&mut heap.0);
+
+        // holes of 32 bytes, all in the same list, the last one freed first
+        let mut allocations = [NonNull::dangling(); 128];
+        for allocation in allocations[..2 * holes].iter_mut() {
+            *allocation = unsafe { tlsf.alloc(32)? };
+        }
+        for allocation in allocations[..2 * holes].iter().step_by(2) {
+            unsafe { tlsf.free(*allocation)? };
+        }
+        assert_eq!(tlsf.listed(32), holes);
+
+        // the head of the list is taken, however many blocks follow it
+        let hole = unsafe { tlsf.alloc(32)? };
+        assert_eq!(hole, allocations[2 * holes - 2]);
+        assert_eq!(tlsf.listed(32), holes - 1);
+        unsafe { tlsf.free(hole)? };
+
+        // larger allocations never look at the list of the holes
+        let _large = unsafe { tlsf.alloc(1000)? };
+        assert_eq!(tlsf.listed(32), holes);
+        assert_eq!(tlsf.free_blocks(), holes + 1);
+    }
+
+    Ok(())
+}
+
+#[test]
+fn test_aligned_alloc() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 8192]);
+    let mut tlsf = empty_backend::<TlsfPalloc>(&mut heap.0[..2048]);
+
+    for align in [16, 64, 256] {
+        let layout = Layout::from_size_align(24, align).unwrap();
+        let ptr = unsafe { tlsf.alloc_layout(layout)? };
+        assert!((ptr.as_ptr() as usize).is_multiple_of(align));
+    }
+
+    // the padding is given back as free blocks
+    assert!(tlsf.free_blocks() > 1);
+
+    Ok(())
+}
+
+#[test]
+fn test_double_free() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 8192]);
+    let mut tlsf = empty_backend::<TlsfPalloc>(&mut heap.0[..512]);
+
+    let allocation = unsafe { tlsf.alloc(24)? };
+    unsafe { tlsf.free(allocation)? };
+
+    let double_free = PallocError::DoubleFree {
+        addr: allocation.as_ptr() as usize,
+        size: 24,
+    };
+    assert_eq!(unsafe { tlsf.free(allocation) }.unwrap_err(), double_free);
+    assert_eq!(
+        unsafe { tlsf.free_checked(allocation) }.unwrap_err(),
+        double_free
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_free_empty() {
+    let mut tlsf = TlsfPalloc::empty();
+    let mut value = 0usize;
+
+    assert_eq!(
+        unsafe { tlsf.free_checked(NonNull::from(&mut value).cast()) },
+        Err(PallocError::ForeignPointer)
+    );
+}
+
+#[test]
+fn test_oom() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 8192]);
+    let mut tlsf = empty_backend::<TlsfPalloc>(&mut heap.0[..256]);
+
+    assert_eq!(
+        unsafe { tlsf.alloc(256) }.unwrap_err(),
+        PallocError::OutOfMemory
+    );
+    assert_eq!(
+        unsafe { tlsf.alloc(usize::MAX / 2) }.unwrap_err(),
+        PallocError::OutOfMemory
+    );
+
+    Ok(())
+}
+
+#[test]
+fn test_random_sequence() -> Result<(), PallocError> {
+    let mut heap = Heap([0; 8192]);
+    let mut tlsf = empty_backend::<TlsfPalloc>(&mut heap.0[..4096]);
+
+    let mut random = Random(0x9e37_79b9);
+
+    let mut allocations = [None; 24];
+    for _ in 0..2000 {
+        let slot = &mut allocations[random.below(24)];
+        match slot.take() {
+            Some(allocation) if random.below(4) == 0 => {
+                let moved = unsafe { tlsf.realloc_layout(allocation, Layout::new::<[u8; 64]>()) };
+                *slot = Some(moved.unwrap_or(allocation));
+            }
+            Some(allocation) => unsafe { tlsf.free(allocation)? },
+            None => {
+                let layout =
+                    Layout::from_size_align(random.below(300), 8 << random.below(4)).unwrap();
+                *slot = unsafe { tlsf.alloc_layout(layout) }.ok();
+            }
+        }
+
+        tlsf.free_blocks();
+    }
+
+    Ok(())
+}